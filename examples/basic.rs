@@ -10,7 +10,7 @@ impl TileBuilder for Builder {
             for col in 0..TILE_SIZE {
                 print!("{:3} ", data[row * TILE_SIZE + col]);
             }
-            print!("\n");
+            println!();
         }
     }
 
@@ -1,10 +1,13 @@
 use std::ffi::{CStr, CString};
 use gl::types::{GLuint, GLint, GLchar, GLenum, GLvoid, GLsizei};
 
-use ochre::{Mat2x2, PathCmd, Rasterizer, TileBuilder, Transform, Vec2, TILE_SIZE};
+use ochre::{LineCap, LineJoin, Mat2x2, PathCmd, Rasterizer, StrokeStyle, TileBuilder, Transform, Vec2, TILE_SIZE};
 
 macro_rules! offset {
-    ($type:ty, $field:ident) => { &(*(0 as *const $type)).$field as *const _ as usize }
+    ($type:ty, $field:ident) => {{
+        let value: $type = std::mem::zeroed();
+        &value.$field as *const _ as usize - &value as *const $type as usize
+    }}
 }
 
 const SCREEN_WIDTH: u32 = 800;
@@ -87,6 +90,7 @@ impl TileBuilder for Builder {
     }
 }
 
+#[allow(clippy::single_match)]
 fn main() {
     let mut events_loop = glutin::EventsLoop::new();
     let window_builder = glutin::WindowBuilder::new()
@@ -150,7 +154,25 @@ fn main() {
                     if let usvg::Paint::Color(color) = s.paint {
                         builder.color = [color.red, color.green, color.blue, s.opacity.to_u8()];
                         let mut rasterizer = Rasterizer::new();
-                        rasterizer.stroke(&path, s.width.value() as f32, transform);
+                        let cap = match s.linecap {
+                            usvg::LineCap::Butt => LineCap::Butt,
+                            usvg::LineCap::Round => LineCap::Round,
+                            usvg::LineCap::Square => LineCap::Square,
+                        };
+                        let join = match s.linejoin {
+                            usvg::LineJoin::Miter => LineJoin::Miter,
+                            usvg::LineJoin::Round => LineJoin::Round,
+                            usvg::LineJoin::Bevel => LineJoin::Bevel,
+                        };
+                        let style = StrokeStyle {
+                            width: s.width.value() as f32,
+                            cap,
+                            join,
+                            miter_limit: s.miterlimit.value() as f32,
+                            dashes: s.dasharray.iter().flatten().map(|dash| *dash as f32).collect(),
+                            dash_offset: s.dashoffset,
+                        };
+                        rasterizer.stroke(&path, &style, transform);
                         rasterizer.finish(builder);
                     }
                 }
@@ -166,8 +188,8 @@ fn main() {
     render(&tree.root(), &mut builder);
 
     let prog = Program::new(
-        &CStr::from_bytes_with_nul(VERT).unwrap(),
-        &CStr::from_bytes_with_nul(FRAG).unwrap()).unwrap();
+        CStr::from_bytes_with_nul(VERT).unwrap(),
+        CStr::from_bytes_with_nul(FRAG).unwrap()).unwrap();
 
     unsafe {
         gl::BlendFunc(gl::SRC_ALPHA, gl::ONE_MINUS_SRC_ALPHA);
@@ -230,7 +252,7 @@ fn main() {
         unsafe {
             gl::ClearColor(1.0, 1.0, 1.0, 1.0);
             gl::Clear(gl::COLOR_BUFFER_BIT);
-            gl::DrawElements(gl::TRIANGLES, builder.indices.len() as GLint, gl::UNSIGNED_INT, 0 as *const std::ffi::c_void);
+            gl::DrawElements(gl::TRIANGLES, builder.indices.len() as GLint, gl::UNSIGNED_INT, std::ptr::null());
         }
 
         context.swap_buffers().unwrap();
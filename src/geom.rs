@@ -1,7 +1,7 @@
 use std::ops;
 
 /// A 2-dimensional vector.
-#[derive(Copy, Clone, Debug, PartialEq)]
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
 pub struct Vec2 {
     pub x: f32,
     pub y: f32,
@@ -11,7 +11,7 @@ impl Vec2 {
     /// Constructs a 2-dimensional vector.
     #[inline]
     pub fn new(x: f32, y: f32) -> Vec2 {
-        Vec2 { x: x, y: y }
+        Vec2 { x, y }
     }
 
     /// Computes the dot product between two vectors.
@@ -128,6 +128,65 @@ impl ops::Mul<Vec2> for f32 {
     }
 }
 
+/// An axis-aligned rectangle.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct Rect {
+    pub min: Vec2,
+    pub max: Vec2,
+}
+
+impl Rect {
+    /// Constructs a rectangle from its minimum and maximum corners.
+    #[inline]
+    pub fn new(min: Vec2, max: Vec2) -> Rect {
+        Rect { min, max }
+    }
+
+    /// Constructs a rectangle containing only the given point.
+    #[inline]
+    pub fn from_point(point: Vec2) -> Rect {
+        Rect { min: point, max: point }
+    }
+
+    /// Computes the width of the rectangle.
+    #[inline]
+    pub fn width(self) -> f32 {
+        self.max.x - self.min.x
+    }
+
+    /// Computes the height of the rectangle.
+    #[inline]
+    pub fn height(self) -> f32 {
+        self.max.y - self.min.y
+    }
+
+    /// Finds the smallest rectangle containing this rectangle and the given
+    /// point.
+    #[inline]
+    pub fn include(self, point: Vec2) -> Rect {
+        Rect {
+            min: self.min.min(point),
+            max: self.max.max(point),
+        }
+    }
+
+    /// Finds the smallest rectangle containing both rectangles.
+    #[inline]
+    pub fn union(self, other: Rect) -> Rect {
+        Rect {
+            min: self.min.min(other.min),
+            max: self.max.max(other.max),
+        }
+    }
+
+    /// Checks whether the given point lies within the rectangle (including
+    /// its boundary).
+    #[inline]
+    pub fn contains(self, point: Vec2) -> bool {
+        point.x >= self.min.x && point.x <= self.max.x && point.y >= self.min.y && point.y <= self.max.y
+    }
+}
+
 /// A 2×2 matrix, in row-major order.
 #[derive(Copy, Clone, Debug, PartialEq)]
 pub struct Mat2x2(pub [f32; 4]);
@@ -152,6 +211,16 @@ impl Mat2x2 {
     pub fn rotate(angle: f32) -> Mat2x2 {
         Mat2x2([angle.cos(), angle.sin(), -angle.sin(), angle.cos()])
     }
+
+    /// Returns the largest factor by which the matrix scales the length of
+    /// any vector (i.e. its largest singular value).
+    pub fn max_scale(self) -> f32 {
+        let [a, b, c, d] = self.0;
+        let sum = a * a + b * b + c * c + d * d;
+        let det = a * d - b * c;
+        let discriminant = (sum * sum - 4.0 * det * det).max(0.0);
+        (0.5 * (sum + discriminant.sqrt())).sqrt()
+    }
 }
 
 impl ops::Mul<Mat2x2> for Mat2x2 {
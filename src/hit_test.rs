@@ -0,0 +1,138 @@
+use crate::path::end_point;
+use crate::rasterizer::TOLERANCE;
+use crate::{stroke_curves, PathCmd, StrokeStyle, Transform, Vec2};
+
+/// A rule for determining which points are inside of a path from the number
+/// of times the path winds around them.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum FillRule {
+    /// Points are inside if the winding number is nonzero.
+    NonZero,
+    /// Points are inside if the winding number is odd.
+    EvenOdd,
+}
+
+/// Computes the contribution of the line segment from `from` to `to` to the
+/// winding number of the path around `point`, using a ray cast in the positive
+/// x direction.
+#[inline]
+fn line_winding(from: Vec2, to: Vec2, point: Vec2) -> i32 {
+    let side = (to - from).cross(point - from);
+    if from.y <= point.y {
+        if to.y > point.y && side > 0.0 {
+            return 1;
+        }
+    } else if to.y <= point.y && side < 0.0 {
+        return -1;
+    }
+    0
+}
+
+/// Computes the winding number of the given path around a point, flattening
+/// curves to within the given tolerance where necessary. Subpaths are
+/// implicitly closed, as when filling.
+fn winding(path: &[PathCmd], point: Vec2, tolerance: f32, transform: Option<Transform>) -> i32 {
+    let mut winding = 0;
+    let mut start = Vec2::new(0.0, 0.0);
+    let mut last = Vec2::new(0.0, 0.0);
+    for command in path {
+        let command = match transform {
+            Some(transform) => command.transform(transform),
+            None => *command,
+        };
+        match command {
+            PathCmd::Move(to) => {
+                winding += line_winding(last, start, point);
+                start = to;
+                last = to;
+            }
+            PathCmd::Line(to) => {
+                winding += line_winding(last, to, point);
+                last = to;
+            }
+            PathCmd::Close => {
+                winding += line_winding(last, start, point);
+                last = start;
+            }
+            _ => {
+                let to = end_point(command).unwrap();
+                let (min, max) = match command {
+                    PathCmd::Quadratic(control, _) | PathCmd::Conic(control, _, _) => {
+                        (last.min(control).min(to), last.max(control).max(to))
+                    }
+                    PathCmd::Cubic(control1, control2, _) => (
+                        last.min(control1).min(control2).min(to),
+                        last.max(control1).max(control2).max(to),
+                    ),
+                    _ => unreachable!(),
+                };
+                // A curve which can't cross the ray contributes nothing, and
+                // one lying entirely to the right of the point crosses it as
+                // many times (with sign) as the line between its endpoints.
+                if point.y < min.y || point.y >= max.y || point.x > max.x {
+                } else if point.x < min.x {
+                    winding += line_winding(last, to, point);
+                } else {
+                    let mut prev = last;
+                    command.flatten(last, tolerance, |cmd| {
+                        if let PathCmd::Line(next) = cmd {
+                            winding += line_winding(prev, next, point);
+                            prev = next;
+                        }
+                    });
+                }
+                last = to;
+            }
+        }
+    }
+    winding + line_winding(last, start, point)
+}
+
+/// Checks whether the given point lies inside of the given path, when filled
+/// using the given fill rule and optionally transformed.
+///
+/// Curves are flattened to within the given tolerance only where they are
+/// close to the point.
+///
+/// ```
+/// use ochre::{contains, FillRule, Path, Vec2};
+///
+/// let mut path = Path::new();
+/// path.circle(Vec2::new(0.0, 0.0), 10.0);
+/// assert!(contains(&path, Vec2::new(7.0, 7.0), FillRule::NonZero, 0.1, None));
+/// assert!(!contains(&path, Vec2::new(7.5, 7.5), FillRule::NonZero, 0.1, None));
+/// ```
+pub fn contains(
+    path: &[PathCmd],
+    point: Vec2,
+    fill_rule: FillRule,
+    tolerance: f32,
+    transform: Option<Transform>,
+) -> bool {
+    let winding = winding(path, point, tolerance, transform);
+    match fill_rule {
+        FillRule::NonZero => winding != 0,
+        FillRule::EvenOdd => winding % 2 != 0,
+    }
+}
+
+/// Checks whether the given point lies inside of the stroke of the given path
+/// with the given style, optionally transformed.
+///
+/// As with [`Rasterizer::stroke`], the stroke is computed before the transform
+/// is applied, so the result matches the area drawn by it.
+///
+/// [`Rasterizer::stroke`]: crate::Rasterizer::stroke
+pub fn stroke_contains(
+    path: &[PathCmd],
+    point: Vec2,
+    style: &StrokeStyle,
+    transform: Option<Transform>,
+) -> bool {
+    let scale = transform.map_or(1.0, |transform| transform.matrix.max_scale());
+    if scale <= 0.0 {
+        return false;
+    }
+    let outline = stroke_curves(path, style, TOLERANCE / scale);
+    contains(&outline, point, FillRule::NonZero, TOLERANCE, transform)
+}
@@ -45,9 +45,19 @@
 
 
 mod geom;
+mod hit_test;
+mod measure;
 mod path;
 mod rasterizer;
+mod segment;
+mod svg;
+mod tile_list;
 
 pub use geom::*;
+pub use hit_test::*;
+pub use measure::*;
 pub use path::*;
 pub use rasterizer::*;
+pub use segment::*;
+pub use svg::*;
+pub use tile_list::*;
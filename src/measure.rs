@@ -0,0 +1,297 @@
+use crate::path::{arc_lengths, curve_normal, end_point, parameter_at, subsegment};
+use crate::{PathCmd, Vec2};
+
+struct MeasuredSegment {
+    last: Vec2,
+    command: PathCmd,
+    /// The distance along the path at which the segment starts.
+    start: f32,
+    /// Arc lengths as returned by `arc_lengths`.
+    lengths: Vec<(f32, f32)>,
+}
+
+impl MeasuredSegment {
+    fn length(&self) -> f32 {
+        self.lengths[self.lengths.len() - 1].1
+    }
+}
+
+struct Contour {
+    /// The index of the contour's first segment.
+    first: usize,
+    length: f32,
+    closed: bool,
+}
+
+/// Measures distances along a path.
+///
+/// `PathMeasure` precomputes the arc length of each segment of a path, after
+/// which it can find the point at a given distance along the path and extract
+/// the part of the path between two distances. Distances are measured along
+/// all of the path's subpaths in order, with the closing segment of a closed
+/// subpath counting toward its length.
+///
+/// ```
+/// use ochre::{PathCmd, PathMeasure, Vec2};
+///
+/// let measure = PathMeasure::new(&[
+///     PathCmd::Move(Vec2::new(0.0, 0.0)),
+///     PathCmd::Line(Vec2::new(3.0, 0.0)),
+///     PathCmd::Line(Vec2::new(3.0, 4.0)),
+/// ]);
+/// assert_eq!(measure.length(), 7.0);
+///
+/// let (position, tangent) = measure.position_and_tangent(5.0).unwrap();
+/// assert_eq!(position, Vec2::new(3.0, 2.0));
+/// assert_eq!(tangent, Vec2::new(0.0, 1.0));
+/// ```
+pub struct PathMeasure {
+    segments: Vec<MeasuredSegment>,
+    contours: Vec<Contour>,
+    length: f32,
+}
+
+impl PathMeasure {
+    /// Measures the given path.
+    pub fn new(path: &[PathCmd]) -> PathMeasure {
+        let mut measure = PathMeasure { segments: Vec::new(), contours: Vec::new(), length: 0.0 };
+
+        let mut start = Vec2::new(0.0, 0.0);
+        let mut last = Vec2::new(0.0, 0.0);
+        let mut in_contour = false;
+        for &command in path {
+            match command {
+                PathCmd::Move(point) => {
+                    start = point;
+                    last = point;
+                    in_contour = false;
+                }
+                PathCmd::Close => {
+                    if in_contour {
+                        if last != start {
+                            measure.push(last, PathCmd::Line(start));
+                        }
+                        let contour = measure.contours.len() - 1;
+                        measure.contours[contour].closed = true;
+                    }
+                    last = start;
+                    in_contour = false;
+                }
+                _ => {
+                    if !in_contour {
+                        measure.contours.push(Contour {
+                            first: measure.segments.len(),
+                            length: 0.0,
+                            closed: false,
+                        });
+                        in_contour = true;
+                    }
+                    measure.push(last, command);
+                    last = end_point(command).unwrap();
+                }
+            }
+        }
+
+        measure
+    }
+
+    fn push(&mut self, last: Vec2, command: PathCmd) {
+        let lengths = arc_lengths(last, command);
+        let length = lengths[lengths.len() - 1].1;
+        self.segments.push(MeasuredSegment { last, command, start: self.length, lengths });
+        let contour = self.contours.len() - 1;
+        self.contours[contour].length += length;
+        self.length += length;
+    }
+
+    /// Returns the total length of the path.
+    pub fn length(&self) -> f32 {
+        self.length
+    }
+
+    /// Returns the length of each subpath of the path, skipping subpaths
+    /// consisting of only a `Move` command.
+    pub fn contour_lengths(&self) -> impl Iterator<Item = f32> + '_ {
+        self.contours.iter().map(|contour| contour.length)
+    }
+
+    /// Returns the distances along the path at which the given contour starts
+    /// and ends.
+    fn contour_range(&self, contour: &Contour) -> (f32, f32) {
+        let start = self.segments[contour.first].start;
+        (start, start + contour.length)
+    }
+
+    /// Finds the segment containing the given distance and the parameter at
+    /// that distance within it.
+    fn locate(&self, distance: f32) -> Option<(&MeasuredSegment, f32)> {
+        let distance = distance.max(0.0).min(self.length);
+        let index = self.segments.partition_point(|segment| segment.start + segment.length() < distance);
+        let segment = self.segments.get(index.min(self.segments.len().saturating_sub(1)))?;
+        Some((segment, parameter_at(&segment.lengths, distance - segment.start)))
+    }
+
+    /// Finds the position and unit tangent vector at the given distance along
+    /// the path, or `None` if the path is empty.
+    ///
+    /// Distances outside of the path are clamped to its start or end.
+    pub fn position_and_tangent(&self, distance: f32) -> Option<(Vec2, Vec2)> {
+        let (segment, t) = self.locate(distance)?;
+        let position = end_point(subsegment(segment.last, segment.command, 0.0, t)).unwrap();
+        let normal = curve_normal(segment.last, segment.command, t);
+        Some((position, Vec2::new(normal.y, -normal.x)))
+    }
+
+    /// Extracts the part of the path between the given distances.
+    ///
+    /// Curves are split into curves of the same type. Each part of a subpath
+    /// in the result begins with a `Move`, and a closed subpath lying entirely
+    /// within the given range remains closed. Distances outside of the path are
+    /// clamped to its start or end, and the result is empty if `start` is not
+    /// less than `end`.
+    pub fn extract(&self, start: f32, end: f32) -> Vec<PathCmd> {
+        let mut output = Vec::new();
+        let start = start.max(0.0);
+        let end = end.min(self.length);
+        if start >= end {
+            return output;
+        }
+
+        for (i, contour) in self.contours.iter().enumerate() {
+            let last_segment = self.contours.get(i + 1).map_or(self.segments.len(), |next| next.first);
+            let (contour_start, contour_end) = self.contour_range(contour);
+            if contour_end <= start || contour_start >= end {
+                continue;
+            }
+
+            let mut moved = false;
+            for segment in &self.segments[contour.first..last_segment] {
+                let segment_end = segment.start + segment.length();
+                if segment_end <= start || segment.start >= end {
+                    continue;
+                }
+
+                let t0 = if start > segment.start {
+                    parameter_at(&segment.lengths, start - segment.start)
+                } else {
+                    0.0
+                };
+                let t1 = if end < segment_end {
+                    parameter_at(&segment.lengths, end - segment.start)
+                } else {
+                    1.0
+                };
+                if !moved {
+                    let point = end_point(subsegment(segment.last, segment.command, 0.0, t0)).unwrap();
+                    output.push(PathCmd::Move(point));
+                    moved = true;
+                }
+                output.push(subsegment(segment.last, segment.command, t0, t1));
+            }
+
+            if contour.closed && start <= contour_start && end >= contour_end {
+                output.push(PathCmd::Close);
+            }
+        }
+
+        output
+    }
+}
+
+/// How [`trim`] treats paths with multiple subpaths.
+///
+/// [`trim`]: crate::trim
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum TrimMode {
+    /// Each subpath is trimmed separately, with the fractions taken of its own
+    /// length.
+    Individually,
+    /// All of the subpaths are trimmed at once, as if they were joined end to
+    /// end, with the fractions taken of the total length of the path.
+    Simultaneously,
+}
+
+/// Appends a part of a path, continuing the previous part if it starts where
+/// the previous part ends.
+fn append(output: &mut Vec<PathCmd>, part: Vec<PathCmd>) {
+    let mut part = part.into_iter().peekable();
+    if let (Some(&PathCmd::Move(point)), Some(&last)) = (part.peek(), output.last()) {
+        if end_point(last) == Some(point) {
+            part.next();
+        }
+    }
+    output.extend(part);
+}
+
+/// Extracts the part of the range from `start` to `start + length` within a
+/// range of distances beginning at `from` and spanning `total`, wrapping
+/// around to the beginning of the range if necessary.
+fn extract_wrapping(
+    output: &mut Vec<PathCmd>,
+    measure: &PathMeasure,
+    from: f32,
+    total: f32,
+    start: f32,
+    length: f32,
+) {
+    if length >= 1.0 {
+        append(output, measure.extract(from, from + total));
+    } else if start + length <= 1.0 {
+        append(output, measure.extract(from + start * total, from + (start + length) * total));
+    } else {
+        append(output, measure.extract(from + start * total, from + total));
+        append(output, measure.extract(from, from + (start + length - 1.0) * total));
+    }
+}
+
+/// Trims the given path to the part between two fractions of its length, as
+/// with the trim path effect of animation tools.
+///
+/// `start` and `end` are fractions between 0 and 1 (swapped if `start` is
+/// greater than `end`), and `offset` shifts both of them along the path,
+/// wrapping around from its end to its start. Curves are split at the exact
+/// parameters corresponding to the trimmed distances, and a part of a closed
+/// subpath that wraps around its start is kept in one piece. The result is
+/// empty if `start` or `end` is not finite.
+///
+/// ```
+/// use ochre::{trim, PathCmd, TrimMode, Vec2};
+///
+/// let path = [PathCmd::Move(Vec2::new(0.0, 0.0)), PathCmd::Line(Vec2::new(10.0, 0.0))];
+/// assert_eq!(
+///     trim(&path, 0.2, 0.5, 0.0, TrimMode::Individually),
+///     [PathCmd::Move(Vec2::new(2.0, 0.0)), PathCmd::Line(Vec2::new(5.0, 0.0))],
+/// );
+/// ```
+pub fn trim(path: &[PathCmd], start: f32, end: f32, offset: f32, mode: TrimMode) -> Vec<PathCmd> {
+    let mut output = Vec::new();
+    if !start.is_finite() || !end.is_finite() {
+        return output;
+    }
+
+    let (start, end) = (start.clamp(0.0, 1.0), end.clamp(0.0, 1.0));
+    let (start, end) = if start <= end { (start, end) } else { (end, start) };
+    let length = end - start;
+    let start = if offset.is_finite() { (start + offset).rem_euclid(1.0) } else { start };
+    if length <= 0.0 {
+        return output;
+    }
+
+    let measure = PathMeasure::new(path);
+    match mode {
+        TrimMode::Individually => {
+            for contour in &measure.contours {
+                // Parts of different subpaths are never joined together.
+                let (from, to) = measure.contour_range(contour);
+                let mut part = Vec::new();
+                extract_wrapping(&mut part, &measure, from, to - from, start, length);
+                output.extend(part);
+            }
+        }
+        TrimMode::Simultaneously => {
+            extract_wrapping(&mut output, &measure, 0.0, measure.length, start, length);
+        }
+    }
+
+    output
+}
@@ -1,7 +1,10 @@
+use std::ops;
+
 use crate::geom::*;
+use crate::segment::Segment;
 
 /// A single command in a 2-dimensional vector path.
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, Debug, PartialEq)]
 pub enum PathCmd {
     Move(Vec2),
     Line(Vec2),
@@ -37,7 +40,13 @@ impl PathCmd {
     }
 
     /// Computes a piecewise-linear approximation of the given path command to
-    /// within the supplied parametric error tolerance.
+    /// within approximately the supplied error tolerance, measured as the
+    /// distance between the command and its approximation.
+    ///
+    /// Quadratic curves are subdivided adaptively using the method described
+    /// by Raph Levien in "Flattening quadratic Béziers", which places points
+    /// more densely where the curvature is higher. Cubic curves are first
+    /// approximated by quadratic curves.
     pub fn flatten(&self, last: Vec2, tolerance: f32, mut callback: impl FnMut(PathCmd)) {
         match *self {
             PathCmd::Move(point) => {
@@ -47,37 +56,46 @@ impl PathCmd {
                 (callback)(PathCmd::Line(point));
             }
             PathCmd::Quadratic(control, point) => {
-                let dt = ((4.0 * tolerance) / (last - 2.0 * control + point).length()).sqrt();
-                let mut t = 0.0;
-                while t < 1.0 {
-                    t = (t + dt).min(1.0);
-                    let p01 = Vec2::lerp(t, last, control);
-                    let p12 = Vec2::lerp(t, control, point);
-                    (callback)(PathCmd::Line(Vec2::lerp(t, p01, p12)));
-                }
+                let segment = Segment::Quadratic { p0: last, p1: control, p2: point };
+                flatten_quadratic(segment, tolerance.sqrt(), &mut callback);
             }
             PathCmd::Cubic(control1, control2, point) => {
-                let a = -1.0 * last + 3.0 * control1 - 3.0 * control2 + point;
-                let b = 3.0 * (last - 2.0 * control1 + control2);
-                let conc = b.length().max((a + b).length());
-                let dt = ((8.0f32.sqrt() * tolerance) / conc).sqrt();
-                let mut t = 0.0;
-                while t < 1.0 {
-                    t = (t + dt).min(1.0);
-                    let p01 = Vec2::lerp(t, last, control1);
-                    let p12 = Vec2::lerp(t, control1, control2);
-                    let p23 = Vec2::lerp(t, control2, point);
-                    let p012 = Vec2::lerp(t, p01, p12);
-                    let p123 = Vec2::lerp(t, p12, p23);
-                    (callback)(PathCmd::Line(Vec2::lerp(t, p012, p123)));
+                // Split the tolerance between the approximation by quadratics
+                // and the flattening of those quadratics.
+                let quad_tolerance = 0.1 * tolerance;
+                let sqrt_tolerance = (tolerance - quad_tolerance).sqrt();
+
+                // The error of approximating a cubic by a single quadratic is
+                // proportional to the length of its third derivative, and
+                // falls off with the cube of the number of pieces.
+                let error = ((3.0 * control2 - point) - (3.0 * control1 - last)).length();
+                // Find the smallest count whose sixth power is at least the
+                // ratio of the squared error to the tolerance. This avoids
+                // `powf`, which isn't correctly rounded, so that the result is
+                // the same on every target.
+                let ratio = error * error / (432.0 * quad_tolerance * quad_tolerance);
+                let sixth_power = |n: usize| {
+                    let cube = (n as f32) * (n as f32) * (n as f32);
+                    cube * cube
+                };
+                let mut count = 1;
+                while sixth_power(count) < ratio {
+                    count += 1;
+                }
+
+                let cubic = Segment::Cubic { p0: last, p1: control1, p2: control2, p3: point };
+                for i in 0..count {
+                    let piece = cubic.subsegment(i as f32 / count as f32, (i + 1) as f32 / count as f32);
+                    if let Segment::Cubic { p0, p1, p2, p3 } = piece {
+                        let control = 0.25 * ((3.0 * p1 - p0) + (3.0 * p2 - p3));
+                        let quad = Segment::Quadratic { p0, p1: control, p2: p3 };
+                        flatten_quadratic(quad, sqrt_tolerance, &mut callback);
+                    }
                 }
             }
             PathCmd::Conic(control, point, weight) => {
                 fn flatten_conic(
-                    last: Vec2,
-                    control: Vec2,
-                    point: Vec2,
-                    weight: f32,
+                    segment: Segment,
                     t0: f32,
                     t1: f32,
                     p0: Vec2,
@@ -86,21 +104,19 @@ impl PathCmd {
                     callback: &mut impl FnMut(PathCmd),
                 ) {
                     let t = 0.5 * (t0 + t1);
-                    let p01 = Vec2::lerp(t, last, weight * control);
-                    let p12 = Vec2::lerp(t, weight * control, point);
-                    let denom = (1.0 - t) * (1.0 - t) + 2.0 * t * (1.0 - t) * weight + t * t;
-                    let midpoint = (1.0 / denom) * Vec2::lerp(t, p01, p12);
+                    let midpoint = segment.eval(t);
                     let err = (midpoint - 0.5 * (p0 + p1)).length();
                     if err > tolerance {
-                        flatten_conic(last, control, point, weight, t0, t, p0, midpoint, tolerance, callback);
-                        flatten_conic(last, control, point, weight, t, t1, midpoint, p1, tolerance, callback);
+                        flatten_conic(segment, t0, t, p0, midpoint, tolerance, callback);
+                        flatten_conic(segment, t, t1, midpoint, p1, tolerance, callback);
                     } else {
                         (callback)(PathCmd::Line(midpoint));
                         (callback)(PathCmd::Line(p1));
                     }
-                };
+                }
 
-                flatten_conic(last, control, point, weight, 0.0, 1.0, last, point, tolerance, &mut callback);
+                let segment = Segment::Conic { p0: last, p1: control, p2: point, weight };
+                flatten_conic(segment, 0.0, 1.0, last, point, tolerance, &mut callback);
             }
             PathCmd::Close => {
                 (callback)(PathCmd::Close);
@@ -109,8 +125,511 @@ impl PathCmd {
     }
 }
 
-/// Computes a piecewise-linear approximation of the given path to within the
-/// supplied parametric error tolerance.
+/// An approximation of the integral of `(1 + 4x^2)^-0.25`, which gives the
+/// number of line segments needed per unit of the parameter when flattening
+/// the parabola `y = x^2`.
+#[inline]
+fn approx_parabola_integral(x: f32) -> f32 {
+    const D: f32 = 0.67;
+    x / (1.0 - D + (D * D * D * D + 0.25 * x * x).sqrt().sqrt())
+}
+
+/// An approximation of the inverse of [`approx_parabola_integral`].
+#[inline]
+fn approx_parabola_inv_integral(x: f32) -> f32 {
+    const B: f32 = 0.39;
+    x * (1.0 - B + (B * B + 0.25 * x * x).sqrt())
+}
+
+/// The parameters for flattening a quadratic curve by mapping it onto a
+/// segment of the parabola `y = x^2`.
+#[derive(Copy, Clone)]
+struct ParabolaParams {
+    a0: f32,
+    a2: f32,
+    u0: f32,
+    uscale: f32,
+    /// The (scaled) number of line segments needed for the curve.
+    val: f32,
+}
+
+impl ParabolaParams {
+    /// Computes the flattening parameters for a quadratic curve, or `None` if
+    /// it is degenerate (i.e. its points are collinear).
+    fn new(quad: Segment, sqrt_tolerance: f32) -> Option<ParabolaParams> {
+        let (p0, p1, p2) = match quad {
+            Segment::Quadratic { p0, p1, p2 } => (p0, p1, p2),
+            _ => return None,
+        };
+        let dd = 2.0 * p1 - p0 - p2;
+        let cross = (p2 - p0).cross(dd);
+        if cross == 0.0 {
+            return None;
+        }
+        let x0 = (p1 - p0).dot(dd) / cross;
+        let x2 = (p2 - p1).dot(dd) / cross;
+        let scale = (cross / (dd.length() * (x2 - x0))).abs();
+        if !scale.is_finite() {
+            return None;
+        }
+
+        let a0 = approx_parabola_integral(x0);
+        let a2 = approx_parabola_integral(x2);
+        let sqrt_scale = scale.sqrt();
+        let val = if x0.signum() == x2.signum() {
+            (a2 - a0).abs() * sqrt_scale
+        } else {
+            // The curve contains the vertex of the parabola, where the
+            // integral is poorly approximated when the scale is large.
+            let xmin = sqrt_tolerance / sqrt_scale;
+            sqrt_tolerance * (a2 - a0).abs() / approx_parabola_integral(xmin)
+        };
+        if !val.is_finite() {
+            return None;
+        }
+
+        let u0 = approx_parabola_inv_integral(a0);
+        let u2 = approx_parabola_inv_integral(a2);
+        Some(ParabolaParams { a0, a2, u0, uscale: 1.0 / (u2 - u0), val })
+    }
+
+    /// Maps a fraction of the number of line segments along the curve to the
+    /// parameter of the curve at which to place a point.
+    #[inline]
+    fn parameter(&self, x: f32) -> f32 {
+        let a = self.a0 + (self.a2 - self.a0) * x;
+        let u = approx_parabola_inv_integral(a);
+        (u - self.u0) * self.uscale
+    }
+}
+
+/// Flattens a quadratic curve, with the number of line segments and their
+/// placement determined by [`ParabolaParams`]. Degenerate curves are replaced
+/// with lines through their extrema.
+fn flatten_quadratic(quad: Segment, sqrt_tolerance: f32, callback: &mut impl FnMut(PathCmd)) {
+    match ParabolaParams::new(quad, sqrt_tolerance) {
+        Some(params) => {
+            let count = ((0.5 * params.val / sqrt_tolerance).ceil() as usize).max(1);
+            for i in 1..count {
+                let t = params.parameter(i as f32 / count as f32);
+                (callback)(PathCmd::Line(quad.eval(t)));
+            }
+        }
+        None => {
+            for t in quad.extrema() {
+                (callback)(PathCmd::Line(quad.eval(t)));
+            }
+        }
+    }
+    (callback)(PathCmd::Line(quad.end()));
+}
+
+/// An owned 2-dimensional vector path.
+///
+/// `Path` keeps track of the current point and the start of the current
+/// subpath, which allows for relative commands. It dereferences to a slice of
+/// [`PathCmd`]s, so it can be passed directly to [`Rasterizer::fill`] and
+/// [`Rasterizer::stroke`].
+///
+/// ```
+/// use ochre::{Path, Vec2};
+///
+/// let mut path = Path::new();
+/// path.move_to(Vec2::new(10.0, 10.0))
+///     .rel_line_to(Vec2::new(20.0, 0.0))
+///     .rel_line_to(Vec2::new(0.0, 20.0))
+///     .close();
+/// assert_eq!(path.current_point(), Vec2::new(10.0, 10.0));
+/// ```
+///
+/// [`PathCmd`]: crate::PathCmd
+/// [`Rasterizer::fill`]: crate::Rasterizer::fill
+/// [`Rasterizer::stroke`]: crate::Rasterizer::stroke
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Path {
+    commands: Vec<PathCmd>,
+    start: Vec2,
+    current: Vec2,
+}
+
+impl Path {
+    /// Constructs an empty path.
+    pub fn new() -> Path {
+        Path::default()
+    }
+
+    /// Returns the current point, i.e. the end point of the last command.
+    pub fn current_point(&self) -> Vec2 {
+        self.current
+    }
+
+    /// Returns the starting point of the current subpath.
+    pub fn subpath_start(&self) -> Vec2 {
+        self.start
+    }
+
+    /// Appends a [`PathCmd`] to the path.
+    ///
+    /// [`PathCmd`]: crate::PathCmd
+    pub fn push(&mut self, command: PathCmd) -> &mut Path {
+        match command {
+            PathCmd::Move(point) => {
+                self.start = point;
+                self.current = point;
+            }
+            PathCmd::Line(point)
+            | PathCmd::Quadratic(_, point)
+            | PathCmd::Cubic(_, _, point)
+            | PathCmd::Conic(_, point, _) => {
+                self.current = point;
+            }
+            PathCmd::Close => {
+                self.current = self.start;
+            }
+        }
+        self.commands.push(command);
+        self
+    }
+
+    /// Begins a new subpath at the given point.
+    pub fn move_to(&mut self, point: Vec2) -> &mut Path {
+        self.push(PathCmd::Move(point))
+    }
+
+    /// Adds a line segment to the given point.
+    pub fn line_to(&mut self, point: Vec2) -> &mut Path {
+        self.push(PathCmd::Line(point))
+    }
+
+    /// Adds a quadratic Bézier curve to the given point.
+    pub fn quad_to(&mut self, control: Vec2, point: Vec2) -> &mut Path {
+        self.push(PathCmd::Quadratic(control, point))
+    }
+
+    /// Adds a cubic Bézier curve to the given point.
+    pub fn cubic_to(&mut self, control1: Vec2, control2: Vec2, point: Vec2) -> &mut Path {
+        self.push(PathCmd::Cubic(control1, control2, point))
+    }
+
+    /// Adds a rational quadratic Bézier curve with the given control point
+    /// weight to the given point.
+    pub fn conic_to(&mut self, control: Vec2, point: Vec2, weight: f32) -> &mut Path {
+        self.push(PathCmd::Conic(control, point, weight))
+    }
+
+    /// Adds an SVG-style elliptical arc to the given point.
+    ///
+    /// See [`arc`] for a description of the parameters.
+    ///
+    /// [`arc`]: crate::arc
+    pub fn arc_to(&mut self, radii: Vec2, x_rotation: f32, large_arc: bool, sweep: bool, point: Vec2) -> &mut Path {
+        arc(self.current, radii, x_rotation, large_arc, sweep, point, |cmd| {
+            self.push(cmd);
+        });
+        self
+    }
+
+    /// Closes the current subpath, returning to its starting point.
+    pub fn close(&mut self) -> &mut Path {
+        self.push(PathCmd::Close)
+    }
+
+    /// Like [`Path::arc_to`], with the end point given relative to the current
+    /// point.
+    pub fn rel_arc_to(&mut self, radii: Vec2, x_rotation: f32, large_arc: bool, sweep: bool, point: Vec2) -> &mut Path {
+        self.arc_to(radii, x_rotation, large_arc, sweep, self.current + point)
+    }
+
+    /// Like [`Path::move_to`], with the point given relative to the current
+    /// point.
+    pub fn rel_move_to(&mut self, point: Vec2) -> &mut Path {
+        self.move_to(self.current + point)
+    }
+
+    /// Like [`Path::line_to`], with the point given relative to the current
+    /// point.
+    pub fn rel_line_to(&mut self, point: Vec2) -> &mut Path {
+        self.line_to(self.current + point)
+    }
+
+    /// Like [`Path::quad_to`], with all points given relative to the current
+    /// point.
+    pub fn rel_quad_to(&mut self, control: Vec2, point: Vec2) -> &mut Path {
+        let current = self.current;
+        self.quad_to(current + control, current + point)
+    }
+
+    /// Like [`Path::cubic_to`], with all points given relative to the current
+    /// point.
+    pub fn rel_cubic_to(&mut self, control1: Vec2, control2: Vec2, point: Vec2) -> &mut Path {
+        let current = self.current;
+        self.cubic_to(current + control1, current + control2, current + point)
+    }
+
+    /// Like [`Path::conic_to`], with all points given relative to the current
+    /// point.
+    pub fn rel_conic_to(&mut self, control: Vec2, point: Vec2, weight: f32) -> &mut Path {
+        let current = self.current;
+        self.conic_to(current + control, current + point, weight)
+    }
+
+    /// Adds a closed subpath for the axis-aligned rectangle with the given
+    /// top-left corner and size.
+    pub fn rect(&mut self, pos: Vec2, size: Vec2) -> &mut Path {
+        self.move_to(pos)
+            .line_to(Vec2::new(pos.x + size.x, pos.y))
+            .line_to(pos + size)
+            .line_to(Vec2::new(pos.x, pos.y + size.y))
+            .close()
+    }
+
+    /// Adds a closed subpath for the axis-aligned rectangle with the given
+    /// top-left corner and size, with rounded corners.
+    ///
+    /// The corner radii are given in the order top-left, top-right,
+    /// bottom-right, bottom-left. If the radii of adjacent corners add up to
+    /// more than the length of the side between them, all radii are scaled
+    /// down proportionally, as in CSS.
+    pub fn rounded_rect(&mut self, pos: Vec2, size: Vec2, radii: [f32; 4]) -> &mut Path {
+        let width = size.x.abs();
+        let height = size.y.abs();
+        let mut scale: f32 = 1.0;
+        for &(side, r1, r2) in &[
+            (width, radii[0], radii[1]),
+            (height, radii[1], radii[2]),
+            (width, radii[2], radii[3]),
+            (height, radii[3], radii[0]),
+        ] {
+            if r1 + r2 > side {
+                scale = scale.min(side / (r1 + r2));
+            }
+        }
+        let [top_left, top_right, bottom_right, bottom_left] = radii;
+        let r = |radius: f32| (radius * scale).max(0.0);
+
+        let x0 = pos.x;
+        let y0 = pos.y;
+        let x1 = pos.x + size.x;
+        let y1 = pos.y + size.y;
+        let dx = size.x.signum();
+        let dy = size.y.signum();
+
+        self.move_to(Vec2::new(x0 + dx * r(top_left), y0));
+        self.line_to(Vec2::new(x1 - dx * r(top_right), y0));
+        self.corner(Vec2::new(x1, y0), Vec2::new(x1, y0 + dy * r(top_right)));
+        self.line_to(Vec2::new(x1, y1 - dy * r(bottom_right)));
+        self.corner(Vec2::new(x1, y1), Vec2::new(x1 - dx * r(bottom_right), y1));
+        self.line_to(Vec2::new(x0 + dx * r(bottom_left), y1));
+        self.corner(Vec2::new(x0, y1), Vec2::new(x0, y1 - dy * r(bottom_left)));
+        self.line_to(Vec2::new(x0, y0 + dy * r(top_left)));
+        self.corner(Vec2::new(x0, y0), Vec2::new(x0 + dx * r(top_left), y0));
+        self.close()
+    }
+
+    /// Adds a quarter-circle arc around a rectangle corner, unless the
+    /// corner has a radius of zero.
+    fn corner(&mut self, corner: Vec2, point: Vec2) {
+        if point != self.current {
+            self.conic_to(corner, point, std::f32::consts::FRAC_1_SQRT_2);
+        }
+    }
+
+    /// Adds a closed subpath for the axis-aligned ellipse with the given center
+    /// and radii.
+    ///
+    /// The ellipse is represented exactly, as four conic segments.
+    pub fn ellipse(&mut self, center: Vec2, radii: Vec2) -> &mut Path {
+        let weight = std::f32::consts::FRAC_1_SQRT_2;
+        let x0 = center.x - radii.x;
+        let y0 = center.y - radii.y;
+        let x1 = center.x + radii.x;
+        let y1 = center.y + radii.y;
+        self.move_to(Vec2::new(x1, center.y))
+            .conic_to(Vec2::new(x1, y1), Vec2::new(center.x, y1), weight)
+            .conic_to(Vec2::new(x0, y1), Vec2::new(x0, center.y), weight)
+            .conic_to(Vec2::new(x0, y0), Vec2::new(center.x, y0), weight)
+            .conic_to(Vec2::new(x1, y0), Vec2::new(x1, center.y), weight)
+            .close()
+    }
+
+    /// Adds a closed subpath for the circle with the given center and radius.
+    ///
+    /// The circle is represented exactly, as four conic segments.
+    pub fn circle(&mut self, center: Vec2, radius: f32) -> &mut Path {
+        self.ellipse(center, Vec2::new(radius, radius))
+    }
+
+    /// Adds a closed subpath for the regular polygon with the given number of
+    /// sides, inscribed in the circle with the given center and radius.
+    ///
+    /// The first vertex lies directly above the center. Nothing is added if
+    /// `sides` is less than 3.
+    pub fn polygon(&mut self, center: Vec2, radius: f32, sides: u32) -> &mut Path {
+        if sides < 3 {
+            return self;
+        }
+
+        for i in 0..sides {
+            let angle = 2.0 * std::f32::consts::PI * i as f32 / sides as f32;
+            let point = center + radius * Vec2::new(angle.sin(), -angle.cos());
+            if i == 0 {
+                self.move_to(point);
+            } else {
+                self.line_to(point);
+            }
+        }
+        self.close()
+    }
+
+    /// Adds a closed subpath for the star with the given number of points,
+    /// alternating between vertices on circles with the given outer and inner
+    /// radii.
+    ///
+    /// The first outer vertex lies directly above the center. Nothing is added
+    /// if `points` is less than 2.
+    pub fn star(&mut self, center: Vec2, outer_radius: f32, inner_radius: f32, points: u32) -> &mut Path {
+        if points < 2 {
+            return self;
+        }
+
+        for i in 0..2 * points {
+            let angle = std::f32::consts::PI * i as f32 / points as f32;
+            let radius = if i % 2 == 0 { outer_radius } else { inner_radius };
+            let point = center + radius * Vec2::new(angle.sin(), -angle.cos());
+            if i == 0 {
+                self.move_to(point);
+            } else {
+                self.line_to(point);
+            }
+        }
+        self.close()
+    }
+
+    /// Removes all commands from the path.
+    pub fn clear(&mut self) {
+        *self = Path::new();
+    }
+}
+
+impl ops::Deref for Path {
+    type Target = [PathCmd];
+
+    fn deref(&self) -> &[PathCmd] {
+        &self.commands
+    }
+}
+
+impl From<Path> for Vec<PathCmd> {
+    fn from(path: Path) -> Vec<PathCmd> {
+        path.commands
+    }
+}
+
+impl From<&[PathCmd]> for Path {
+    fn from(commands: &[PathCmd]) -> Path {
+        commands.iter().copied().collect()
+    }
+}
+
+impl std::iter::FromIterator<PathCmd> for Path {
+    fn from_iter<I: IntoIterator<Item = PathCmd>>(iter: I) -> Path {
+        let mut path = Path::new();
+        path.extend(iter);
+        path
+    }
+}
+
+impl Extend<PathCmd> for Path {
+    fn extend<I: IntoIterator<Item = PathCmd>>(&mut self, iter: I) {
+        for command in iter {
+            self.push(command);
+        }
+    }
+}
+
+/// Converts an SVG-style elliptical arc into a sequence of conic segments.
+///
+/// The arc goes from `from` to `to` along an ellipse with the given radii,
+/// with its x-axis rotated by `x_rotation` radians. Out of the four possible
+/// arcs, `large_arc` selects one spanning more than 180 degrees, and `sweep`
+/// selects one going in the direction of increasing angle.
+///
+/// As in the SVG specification, the arc is omitted if the endpoints are equal,
+/// a straight line is produced if either radius is zero, and the radii are
+/// scaled up if they are too small for the ellipse to reach both endpoints.
+pub fn arc(
+    from: Vec2,
+    radii: Vec2,
+    x_rotation: f32,
+    large_arc: bool,
+    sweep: bool,
+    to: Vec2,
+    mut callback: impl FnMut(PathCmd),
+) {
+    use std::f32::consts::{FRAC_PI_2, PI};
+
+    if from == to {
+        return;
+    }
+
+    let mut rx = radii.x.abs();
+    let mut ry = radii.y.abs();
+    if rx == 0.0 || ry == 0.0 || !rx.is_finite() || !ry.is_finite() {
+        (callback)(PathCmd::Line(to));
+        return;
+    }
+
+    let (sin, cos) = x_rotation.sin_cos();
+    let rotate = |v: Vec2| Vec2::new(cos * v.x - sin * v.y, sin * v.x + cos * v.y);
+    let unrotate = |v: Vec2| Vec2::new(cos * v.x + sin * v.y, -sin * v.x + cos * v.y);
+
+    let half = unrotate(0.5 * (from - to));
+    let lambda = (half.x * half.x) / (rx * rx) + (half.y * half.y) / (ry * ry);
+    if lambda > 1.0 {
+        rx *= lambda.sqrt();
+        ry *= lambda.sqrt();
+    }
+
+    let num = rx * rx * ry * ry - rx * rx * half.y * half.y - ry * ry * half.x * half.x;
+    let denom = rx * rx * half.y * half.y + ry * ry * half.x * half.x;
+    let mut coef = (num / denom).max(0.0).sqrt();
+    if large_arc == sweep {
+        coef = -coef;
+    }
+    let center_prime = coef * Vec2::new(rx * half.y / ry, -ry * half.x / rx);
+    let center = rotate(center_prime) + 0.5 * (from + to);
+
+    let angle = |v: Vec2| v.y.atan2(v.x);
+    let start_angle = angle(Vec2::new((half.x - center_prime.x) / rx, (half.y - center_prime.y) / ry));
+    let end_angle = angle(Vec2::new((-half.x - center_prime.x) / rx, (-half.y - center_prime.y) / ry));
+    let mut sweep_angle = end_angle - start_angle;
+    if sweep && sweep_angle < 0.0 {
+        sweep_angle += 2.0 * PI;
+    } else if !sweep && sweep_angle > 0.0 {
+        sweep_angle -= 2.0 * PI;
+    }
+
+    let point_at = |v: Vec2| center + rotate(Vec2::new(rx * v.x, ry * v.y));
+
+    let segments = (sweep_angle.abs() / FRAC_PI_2 - 1e-3).ceil().max(1.0) as u32;
+    let step = sweep_angle / segments as f32;
+    let weight = (0.5 * step).cos();
+    for i in 0..segments {
+        let mid = start_angle + (i as f32 + 0.5) * step;
+        let control = point_at((1.0 / weight) * Vec2::new(mid.cos(), mid.sin()));
+        let point = if i + 1 == segments {
+            to
+        } else {
+            let end = start_angle + (i + 1) as f32 * step;
+            point_at(Vec2::new(end.cos(), end.sin()))
+        };
+        (callback)(PathCmd::Conic(control, point, weight));
+    }
+}
+
+/// Computes a piecewise-linear approximation of the given path to within
+/// approximately the supplied error tolerance, measured as the distance
+/// between the path and its approximation.
 pub fn flatten(path: &[PathCmd], tolerance: f32) -> Vec<PathCmd> {
     let mut last = Vec2::new(0.0, 0.0);
     let mut output = Vec::new();
@@ -143,132 +662,1517 @@ pub fn flatten(path: &[PathCmd], tolerance: f32) -> Vec<PathCmd> {
     output
 }
 
-/// Converts the given path to a stroked path with the given width.
+/// Computes the exact bounding box of the given path, optionally applying a
+/// transform to it first, or `None` if the path contains no points.
 ///
-/// This function will panic if the given path is not piecewise-linear (i.e. if
-/// it contains [`PathCmd`]s other than `Move`, `Line`, or `Close`.
+/// Curves are bounded by their endpoints and the points where they reach an
+/// extremum in x or y, so the result can be smaller than that of
+/// [`control_bounds`].
 ///
-/// The line-cap style is "butt" and the line-join style is "miter."
-pub fn stroke(polygon: &[PathCmd], width: f32) -> Vec<PathCmd> {
-    #[inline]
-    fn get_point(command: PathCmd) -> Vec2 {
+/// ```
+/// use ochre::{bounds, PathCmd, Rect, Vec2};
+///
+/// let path = [
+///     PathCmd::Move(Vec2::new(0.0, 0.0)),
+///     PathCmd::Quadratic(Vec2::new(1.0, 2.0), Vec2::new(2.0, 0.0)),
+/// ];
+/// assert_eq!(bounds(&path, None), Some(Rect::new(Vec2::new(0.0, 0.0), Vec2::new(2.0, 1.0))));
+/// ```
+///
+/// [`control_bounds`]: crate::control_bounds
+pub fn bounds(path: &[PathCmd], transform: Option<Transform>) -> Option<Rect> {
+    let mut rect: Option<Rect> = None;
+    let mut include = |point: Vec2| {
+        rect = Some(match rect {
+            Some(rect) => rect.include(point),
+            None => Rect::from_point(point),
+        });
+    };
+
+    let mut last = Vec2::new(0.0, 0.0);
+    for command in path {
+        let command = match transform {
+            Some(transform) => command.transform(transform),
+            None => *command,
+        };
         match command {
-            PathCmd::Move(point) => point,
-            PathCmd::Line(point) => point,
-            _ => unreachable!(),
+            PathCmd::Move(point) | PathCmd::Line(point) => {
+                include(point);
+            }
+            PathCmd::Quadratic(_, point) | PathCmd::Cubic(_, _, point) | PathCmd::Conic(_, point, _) => {
+                let segment = Segment::new(last, command).unwrap();
+                for t in segment.extrema() {
+                    include(segment.eval(t));
+                }
+                include(point);
+            }
+            PathCmd::Close => {}
+        }
+        if let Some(point) = end_point(command) {
+            last = point;
         }
     }
 
-    #[inline]
-    fn join(path: &mut Vec<PathCmd>, width: f32, prev_normal: Vec2, next_normal: Vec2, point: Vec2) {
-        let offset = 1.0 / (1.0 + prev_normal.dot(next_normal));
-        if offset.abs() > 2.0 {
-            path.push(PathCmd::Line(point + 0.5 * width * prev_normal));
-            path.push(PathCmd::Line(point + 0.5 * width * next_normal));
-        } else {
-            path.push(PathCmd::Line(point + 0.5 * width * offset * (prev_normal + next_normal)));
+    rect
+}
+
+/// Computes the bounding box of all of the points of the given path,
+/// including the control points of curves, optionally applying a transform to
+/// it first, or `None` if the path contains no points.
+///
+/// This is faster than [`bounds`] and contains the same area, but may be
+/// larger.
+///
+/// [`bounds`]: crate::bounds
+pub fn control_bounds(path: &[PathCmd], transform: Option<Transform>) -> Option<Rect> {
+    let mut rect: Option<Rect> = None;
+    for command in path {
+        let command = match transform {
+            Some(transform) => command.transform(transform),
+            None => *command,
+        };
+        let points = match command {
+            PathCmd::Move(point) | PathCmd::Line(point) => [point, point, point],
+            PathCmd::Quadratic(control, point) | PathCmd::Conic(control, point, _) => [control, point, point],
+            PathCmd::Cubic(control1, control2, point) => [control1, control2, point],
+            PathCmd::Close => continue,
+        };
+        for point in points {
+            rect = Some(match rect {
+                Some(rect) => rect.include(point),
+                None => Rect::from_point(point),
+            });
         }
     }
 
-    #[inline]
-    fn offset(path: &mut Vec<PathCmd>, width: f32, contour: &[PathCmd], closed: bool, reverse: bool) {
-        let first_point = if closed == reverse {
-            get_point(contour[0])
+    rect
+}
+
+#[inline]
+pub(crate) fn end_point(command: PathCmd) -> Option<Vec2> {
+    match command {
+        PathCmd::Move(point) => Some(point),
+        PathCmd::Line(point) => Some(point),
+        PathCmd::Quadratic(_, point) => Some(point),
+        PathCmd::Cubic(_, _, point) => Some(point),
+        PathCmd::Conic(_, point, _) => Some(point),
+        PathCmd::Close => None,
+    }
+}
+
+/// Returns the portion of the segment starting at `last` between the parameters
+/// `t0` and `t1`, as a command starting at the point at `t0`.
+pub(crate) fn subsegment(last: Vec2, command: PathCmd, t0: f32, t1: f32) -> PathCmd {
+    match Segment::new(last, command) {
+        Some(segment) => segment.subsegment(t0, t1).command(),
+        None => command,
+    }
+}
+
+#[inline]
+fn point_at(last: Vec2, command: PathCmd, t: f32) -> Vec2 {
+    Segment::new(last, command).map_or(last, |segment| segment.eval(t))
+}
+
+/// Computes a piecewise-linear approximation of the arc length of the segment
+/// starting at `last` as a function of its parameter, as a list of
+/// `(parameter, length)` pairs starting with `(0, 0)`.
+pub(crate) fn arc_lengths(last: Vec2, command: PathCmd) -> Vec<(f32, f32)> {
+    #[allow(clippy::too_many_arguments)]
+    fn subdivide(
+        last: Vec2,
+        command: PathCmd,
+        t0: f32,
+        p0: Vec2,
+        t1: f32,
+        p1: Vec2,
+        depth: u32,
+        table: &mut Vec<(f32, f32)>,
+    ) {
+        let t = 0.5 * (t0 + t1);
+        let p = point_at(last, command, t);
+        let chord = (p1 - p0).length();
+        let split = (p - p0).length() + (p1 - p).length();
+        if depth < 16 && (depth < 3 || split - chord > 1e-3) {
+            subdivide(last, command, t0, p0, t, p, depth + 1, table);
+            subdivide(last, command, t, p, t1, p1, depth + 1, table);
         } else {
-            get_point(*contour.last().unwrap())
-        };
-        let mut prev_point = first_point;
-        let mut prev_normal = Vec2::new(0.0, 0.0);
-        let mut i = 0;
-        loop {
-            let next_point = if i < contour.len() {
-                if reverse {
-                    get_point(contour[contour.len() - i - 1])
-                } else {
-                    get_point(contour[i])
-                }
-            } else {
-                first_point
-            };
+            // The error of the polyline length decreases quadratically with
+            // each subdivision, so extrapolate from the two estimates.
+            let length = table[table.len() - 1].1;
+            table.push((t1, length + split + (split - chord) / 3.0));
+        }
+    }
 
-            if next_point != prev_point || i == contour.len() {
-                let next_tangent = next_point - prev_point;
-                let next_normal = Vec2::new(-next_tangent.y, next_tangent.x);
-                let next_normal_len = next_normal.length();
-                let next_normal = if next_normal_len == 0.0 {
-                    Vec2::new(0.0, 0.0)
-                } else {
-                    next_normal * (1.0 / next_normal_len)
-                };
+    let mut table = vec![(0.0, 0.0)];
+    let end = end_point(command).unwrap_or(last);
+    if let PathCmd::Line(point) = command {
+        table.push((1.0, (point - last).length()));
+    } else {
+        subdivide(last, command, 0.0, last, 1.0, end, 0, &mut table);
+    }
+    table
+}
 
-                join(path, width, prev_normal, next_normal, prev_point);
+/// Finds the parameter at the given arc length in a table returned by
+/// `arc_lengths`.
+pub(crate) fn parameter_at(table: &[(f32, f32)], length: f32) -> f32 {
+    let i = table.partition_point(|&(_, l)| l < length).max(1).min(table.len() - 1);
+    let (t0, l0) = table[i - 1];
+    let (t1, l1) = table[i];
+    if l1 > l0 {
+        t0 + (t1 - t0) * ((length - l0) / (l1 - l0)).clamp(0.0, 1.0)
+    } else {
+        t1
+    }
+}
 
-                prev_point = next_point;
-                prev_normal = next_normal;
-            }
+struct Dasher<'a> {
+    output: Vec<PathCmd>,
+    dashes: &'a [f32],
+    intervals: usize,
+    start_index: usize,
+    start_remaining: f32,
+    index: usize,
+    remaining: f32,
+    contour_base: usize,
+    first_dash_end: Option<usize>,
+    toggled: bool,
+}
 
-            i += 1;
-            if i > contour.len() {
-                break;
+impl<'a> Dasher<'a> {
+    fn on(&self) -> bool {
+        self.index & 1 == 0
+    }
+
+    fn begin_contour(&mut self, point: Vec2) {
+        self.index = self.start_index;
+        self.remaining = self.start_remaining;
+        self.contour_base = self.output.len();
+        self.first_dash_end = None;
+        self.toggled = false;
+        if self.on() {
+            self.output.push(PathCmd::Move(point));
+            if self.remaining == 0.0 {
+                self.output.push(PathCmd::Line(point));
             }
         }
     }
 
-    let mut output = Vec::new();
-
-    let mut contour_start = 0;
-    let mut contour_end = 0;
-    let mut closed = false;
-    let mut commands = polygon.iter();
-    loop {
-        let command = commands.next();
+    fn advance(&mut self, point: Vec2) {
+        if !self.toggled && self.on() {
+            self.first_dash_end = Some(self.output.len());
+        }
+        self.toggled = true;
 
-        if let Some(PathCmd::Close) = command {
-            closed = true;
+        self.index = (self.index + 1) % self.intervals;
+        self.remaining = self.dashes[self.index % self.dashes.len()];
+        if self.on() {
+            self.output.push(PathCmd::Move(point));
+            if self.remaining == 0.0 {
+                // Zero-length dashes are emitted as zero-length subpaths, so
+                // that they are drawn as dots or squares by round or square
+                // caps.
+                self.output.push(PathCmd::Line(point));
+            }
         }
+    }
 
-        if let None | Some(PathCmd::Move(_)) | Some(PathCmd::Close) = command {
-            if contour_start != contour_end {
-                let contour = &polygon[contour_start..contour_end];
+    fn segment(&mut self, last: Vec2, command: PathCmd) {
+        let table = arc_lengths(last, command);
+        let length = table[table.len() - 1].1;
 
-                let base = output.len();
-                offset(&mut output, width, contour, closed, false);
-                output[base] = PathCmd::Move(get_point(output[base]));
-                if closed {
-                    output.push(PathCmd::Close);
-                }
+        let mut position = 0.0;
+        let mut t = 0.0;
+        loop {
+            let step = self.remaining.min(length - position);
+            position += step;
+            self.remaining -= step;
 
-                let base = output.len();
-                offset(&mut output, width, contour, closed, true);
-                if closed {
-                    output[base] = PathCmd::Move(get_point(output[base]));
-                }
-                output.push(PathCmd::Close);
+            let next_t = if self.remaining > 0.0 { 1.0 } else { parameter_at(&table, position) };
+            if self.on() && step > 0.0 {
+                self.output.push(subsegment(last, command, t, next_t));
+            }
+            t = next_t;
+
+            if self.remaining > 0.0 {
+                break;
+            }
+            self.advance(point_at(last, command, t));
+        }
+    }
+
+    fn end_contour(&mut self, closed: bool) {
+        if !self.toggled {
+            if self.on() && closed {
+                self.output.push(PathCmd::Close);
+            }
+        } else if closed && self.on() {
+            // The contour ends partway through a dash; since it is closed,
+            // continue that dash into the first one.
+            if let Some(first_dash_end) = self.first_dash_end {
+                let first_dash = self.output[self.contour_base + 1..first_dash_end].to_vec();
+                self.output.extend(first_dash);
+                self.output.drain(self.contour_base..first_dash_end);
             }
         }
+    }
+}
+
+/// Splits the given path into dashes along its arc length.
+///
+/// The dash array alternates between the lengths of dashes and the lengths of
+/// the gaps between them, and the offset gives the distance into the dash
+/// pattern at which each subpath begins. As in SVG, an array with an odd number
+/// of values is repeated to yield an even number of values. If the array is
+/// empty, contains negative values, or sums to zero, the path is returned
+/// unchanged.
+///
+/// Curves are split into curves of the same type. Dashes which span the start
+/// of a closed subpath are joined together, and zero-length dashes are emitted
+/// as zero-length subpaths, so that they are drawn as dots or squares by
+/// [`stroke`] with round or square caps.
+///
+/// [`stroke`]: crate::stroke
+pub fn dash(path: &[PathCmd], dashes: &[f32], offset: f32) -> Vec<PathCmd> {
+    let total: f32 = dashes.iter().sum();
+    let valid = dashes.iter().all(|dash| *dash >= 0.0 && dash.is_finite());
+    if !(valid && total > 0.0 && total.is_finite()) {
+        return path.to_vec();
+    }
+
+    let intervals = if dashes.len() % 2 == 1 { 2 * dashes.len() } else { dashes.len() };
+    let pattern_length = total * (intervals / dashes.len()) as f32;
 
-        if let Some(command) = command {
-            match command {
-                PathCmd::Move(_) => {
-                    contour_start = contour_end;
-                    contour_end = contour_start + 1;
+    // Find the position in the dash pattern at which each subpath begins.
+    // Intervals of positive length ending exactly at the offset are skipped,
+    // but zero-length dashes at the offset are kept.
+    let mut offset = if offset.is_finite() { offset.rem_euclid(pattern_length) } else { 0.0 };
+    let mut start_index = 0;
+    while offset > dashes[start_index % dashes.len()]
+        || (offset == dashes[start_index % dashes.len()] && offset > 0.0)
+    {
+        offset -= dashes[start_index % dashes.len()];
+        start_index = (start_index + 1) % intervals;
+    }
+    let start_remaining = dashes[start_index % dashes.len()] - offset;
+
+    let mut dasher = Dasher {
+        output: Vec::new(),
+        dashes,
+        intervals,
+        start_index,
+        start_remaining,
+        index: start_index,
+        remaining: start_remaining,
+        contour_base: 0,
+        first_dash_end: None,
+        toggled: false,
+    };
+
+    let mut start = Vec2::new(0.0, 0.0);
+    let mut last = Vec2::new(0.0, 0.0);
+    let mut in_contour = false;
+    for &command in path {
+        match command {
+            PathCmd::Move(point) => {
+                if in_contour {
+                    dasher.end_contour(false);
                 }
-                PathCmd::Line(_) => {
-                    contour_end += 1;
+                start = point;
+                last = point;
+                in_contour = false;
+            }
+            PathCmd::Close => {
+                if !in_contour {
+                    dasher.begin_contour(last);
                 }
-                PathCmd::Close => {
-                    contour_start = contour_end + 1;
-                    contour_end = contour_start;
-                    closed = true;
+                if last != start {
+                    dasher.segment(last, PathCmd::Line(start));
                 }
-                _ => {
-                    panic!();
+                dasher.end_contour(true);
+                last = start;
+                in_contour = false;
+            }
+            _ => {
+                if !in_contour {
+                    dasher.begin_contour(last);
+                    in_contour = true;
                 }
+                dasher.segment(last, command);
+                last = end_point(command).unwrap();
             }
-        } else {
-            break;
         }
     }
+    if in_contour {
+        dasher.end_contour(false);
+    }
+
+    dasher.output
+}
+
+/// The shape used at the ends of open subpaths when stroking.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum LineCap {
+    /// The stroke ends flush with the endpoint.
+    Butt,
+    /// The stroke ends with a semicircle centered on the endpoint.
+    Round,
+    /// The stroke ends with a square extending past the endpoint by half the
+    /// stroke width.
+    Square,
+}
+
+/// The shape used where two segments of a stroked path meet.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum LineJoin {
+    /// The outer edges of the segments are extended until they meet. If the
+    /// miter limit is exceeded, a bevel join is used instead.
+    Miter,
+    /// Like `Miter`, but if the miter limit is exceeded, the miter is clipped
+    /// at a distance of half the miter limit times the stroke width from the
+    /// join point instead of being replaced with a bevel.
+    MiterClip,
+    /// The segments are joined by a circular arc centered on the join point.
+    Round,
+    /// The outer corners of the segments are connected by a straight line.
+    Bevel,
+    /// The outer edges of the segments are extended with circular arcs
+    /// matching their curvature at the join until they meet, as with the SVG 2
+    /// `arcs` join. If the miter limit is exceeded, the join is clipped at a
+    /// distance of half the miter limit times the stroke width from the join
+    /// point, along the line through the point where the arcs meet. Where both
+    /// segments are straight, or the arcs don't meet, this is the same as
+    /// `MiterClip`.
+    Arcs,
+}
+
+/// Parameters for converting a path to a stroke.
+#[derive(Clone, Debug, PartialEq)]
+pub struct StrokeStyle {
+    /// The width of the stroke.
+    pub width: f32,
+    /// The shape used at the ends of open subpaths.
+    pub cap: LineCap,
+    /// The shape used where two segments meet.
+    pub join: LineJoin,
+    /// The limit on the ratio of the miter length to the stroke width for
+    /// `Miter`, `MiterClip` and `Arcs` joins.
+    pub miter_limit: f32,
+    /// The lengths of alternating dashes and gaps, or an empty array for a
+    /// solid stroke. See [`dash`] for details.
+    ///
+    /// [`dash`]: crate::dash
+    pub dashes: Vec<f32>,
+    /// The distance into the dash pattern at which each subpath begins.
+    pub dash_offset: f32,
+}
 
+impl StrokeStyle {
+    /// Creates a solid stroke style with the given width, butt caps and miter
+    /// joins with a miter limit of 4, matching the SVG defaults.
+    pub fn new(width: f32) -> StrokeStyle {
+        StrokeStyle {
+            width,
+            cap: LineCap::Butt,
+            join: LineJoin::Miter,
+            miter_limit: 4.0,
+            dashes: Vec::new(),
+            dash_offset: 0.0,
+        }
+    }
+}
+
+impl Default for StrokeStyle {
+    fn default() -> StrokeStyle {
+        StrokeStyle::new(1.0)
+    }
+}
+
+#[inline]
+fn normal(from: Vec2, to: Vec2) -> Vec2 {
+    let tangent = to - from;
+    let normal = Vec2::new(-tangent.y, tangent.x);
+    let length = normal.length();
+    if length == 0.0 {
+        Vec2::new(0.0, 0.0)
+    } else {
+        normal * (1.0 / length)
+    }
+}
+
+/// Joins the offset segments on the left side of two segments meeting at
+/// `point` with the given normals, ending at the start of the second offset
+/// segment. The lengths of the segments limit where the inner side of the join
+/// may be cut off, and their signed curvatures at the join (positive when
+/// turning toward the normal) determine the shape of `Arcs` joins.
+#[inline]
+#[allow(clippy::too_many_arguments)]
+fn join(
+    output: &mut Vec<PathCmd>,
+    style: &StrokeStyle,
+    point: Vec2,
+    prev_normal: Vec2,
+    next_normal: Vec2,
+    prev_length: f32,
+    next_length: f32,
+    prev_curvature: f32,
+    next_curvature: f32,
+) {
+    let half_width = 0.5 * style.width;
+    let prev_dir = Vec2::new(prev_normal.y, -prev_normal.x);
+    let next_dir = Vec2::new(next_normal.y, -next_normal.x);
+
+    let cos = prev_normal.dot(next_normal);
+    let miter = point + (half_width / (1.0 + cos)) * (prev_normal + next_normal);
+
+    if prev_dir.dot(next_normal) < 0.0 {
+        // This is the inner side of the join. If the offset segments intersect
+        // within the half of each segment adjacent to the join (so that the
+        // intersection can't overlap with the one at the segment's other end),
+        // join them at the intersection; otherwise, connect them through the
+        // join point so that the stroke is still covered.
+        let distance = 2.0 * (miter - point).dot(prev_dir).abs();
+        if distance <= prev_length && distance <= next_length {
+            output.push(PathCmd::Line(miter));
+        } else {
+            output.push(PathCmd::Line(point + half_width * prev_normal));
+            output.push(PathCmd::Line(point));
+            output.push(PathCmd::Line(point + half_width * next_normal));
+        }
+        return;
+    }
+
+    // The ratio of the miter length to the stroke width is
+    // 1 / sin(theta / 2) = sqrt(2 / (1 + cos)), where theta is the angle
+    // between the segments and cos is the cosine of the angle between the
+    // normals.
+    let within_limit = style.miter_limit * style.miter_limit * (1.0 + cos) >= 2.0;
+
+    // Nearly tangent segments are joined as with `MiterClip`, since their
+    // extended edges nearly coincide and their intersections can't be found
+    // reliably.
+    if style.join == LineJoin::Arcs
+        && (prev_curvature != 0.0 || next_curvature != 0.0)
+        && cos < 1.0 - 1e-4
+        && arcs_join(output, style, point, prev_normal, next_normal, prev_curvature, next_curvature)
+    {
+        return;
+    }
+
+    match style.join {
+        LineJoin::Miter | LineJoin::MiterClip | LineJoin::Arcs if within_limit => {
+            output.push(PathCmd::Line(miter));
+        }
+        LineJoin::MiterClip | LineJoin::Arcs => {
+            let bisector = prev_normal + next_normal;
+            let bisector = if bisector.length() == 0.0 {
+                prev_dir
+            } else {
+                bisector * (1.0 / bisector.length())
+            };
+            let clip = style.miter_limit * half_width;
+            let prev_extent = (clip - half_width * prev_normal.dot(bisector)) / prev_dir.dot(bisector);
+            let next_extent = (clip - half_width * next_normal.dot(bisector)) / -next_dir.dot(bisector);
+            output.push(PathCmd::Line(point + half_width * prev_normal));
+            output.push(PathCmd::Line(point + half_width * prev_normal + prev_extent * prev_dir));
+            output.push(PathCmd::Line(point + half_width * next_normal - next_extent * next_dir));
+            output.push(PathCmd::Line(point + half_width * next_normal));
+        }
+        LineJoin::Round => {
+            // Split the arc into at most two pieces, each spanning no more
+            // than 90 degrees, at the normal halfway between the two. The
+            // weight of each piece is the cosine of half the angle it spans,
+            // found with the half-angle identity to avoid trigonometric
+            // functions.
+            let middle = prev_normal + next_normal;
+            let middle = if middle.length() == 0.0 {
+                prev_dir
+            } else {
+                middle * (1.0 / middle.length())
+            };
+            let pieces: &[Vec2] = if cos < 0.0 { &[middle, next_normal] } else { &[next_normal] };
+            let mut from = prev_normal;
+            output.push(PathCmd::Line(point + half_width * from));
+            for &to in pieces {
+                let cos = from.dot(to);
+                let weight = (0.5 * (1.0 + cos)).sqrt();
+                let control = point + (half_width / (1.0 + cos)) * (from + to);
+                output.push(PathCmd::Conic(control, point + half_width * to, weight));
+                from = to;
+            }
+        }
+        LineJoin::Miter | LineJoin::Bevel => {
+            output.push(PathCmd::Line(point + half_width * prev_normal));
+            output.push(PathCmd::Line(point + half_width * next_normal));
+        }
+    }
+}
+
+/// One of the outer edges of an `Arcs` join, extended from the end of an
+/// offset segment in the direction `dir` away from it: either the circle
+/// matching the curvature of the offset segment, or its tangent line.
+#[derive(Copy, Clone)]
+struct Extension {
+    origin: Vec2,
+    dir: Vec2,
+    /// The center and radius of the circle, if the edge is curved.
+    circle: Option<(Vec2, f32)>,
+}
+
+impl Extension {
+    fn new(origin: Vec2, dir: Vec2, normal: Vec2, curvature: f32, half_width: f32) -> Extension {
+        // The offset segment has the same center of curvature as the segment
+        // itself, which lies at a signed distance of 1 / curvature along the
+        // normal.
+        let radius = 1.0 / curvature - half_width;
+        let circle = if radius.is_finite() && radius != 0.0 {
+            Some((origin + radius * normal, radius.abs()))
+        } else {
+            None
+        };
+        Extension { origin, dir, circle }
+    }
+
+    /// Returns the point reached after travelling the given distance along a
+    /// line or angle around a circle.
+    fn point_at(&self, travel: f32) -> Vec2 {
+        match self.circle {
+            Some((center, radius)) => {
+                let radial = (1.0 / radius) * (self.origin - center);
+                let (sin, cos) = travel.sin_cos();
+                center + radius * (cos * radial + sin * self.dir)
+            }
+            None => self.origin + travel * self.dir,
+        }
+    }
+
+    /// Returns the distance or angle travelled to reach the given point, which
+    /// is assumed to lie on the extension.
+    fn travel(&self, point: Vec2) -> f32 {
+        match self.circle {
+            Some((center, radius)) => {
+                let radial = (1.0 / radius) * (self.origin - center);
+                let angle = self.dir.dot(point - center).atan2(radial.dot(point - center));
+                if angle < 0.0 {
+                    angle + 2.0 * std::f32::consts::PI
+                } else {
+                    angle
+                }
+            }
+            None => (point - self.origin).dot(self.dir),
+        }
+    }
+
+    /// Returns the smallest travel at which the extension reaches the line of
+    /// points `p` with `axis.dot(p) == distance`, if any.
+    fn travel_to_line(&self, axis: Vec2, distance: f32) -> Option<f32> {
+        match self.circle {
+            Some((center, radius)) => {
+                let radial = (1.0 / radius) * (self.origin - center);
+                let (a, b) = (axis.dot(radial), axis.dot(self.dir));
+                let amplitude = (a * a + b * b).sqrt();
+                let cos = (distance - axis.dot(center)) / (radius * amplitude);
+                if !(-1.0..=1.0).contains(&cos) {
+                    return None;
+                }
+                let phase = b.atan2(a);
+                let offset = cos.acos();
+                let tau = 2.0 * std::f32::consts::PI;
+                let first = (phase - offset).rem_euclid(tau);
+                let second = (phase + offset).rem_euclid(tau);
+                Some(first.min(second))
+            }
+            None => {
+                let speed = axis.dot(self.dir);
+                if speed == 0.0 {
+                    return None;
+                }
+                Some((distance - axis.dot(self.origin)) / speed)
+            }
+        }
+    }
+
+    /// Checks whether the given travel lies within the part of the extension
+    /// used for joins: forward along a line, or at most half of a circle.
+    fn is_valid(&self, travel: f32) -> bool {
+        match self.circle {
+            Some(_) => travel <= std::f32::consts::PI,
+            None => travel >= 0.0,
+        }
+    }
+
+    /// Appends the part of the extension from its origin up to the given
+    /// travel, or back from there to its origin if `reverse` is set.
+    fn draw(&self, output: &mut Vec<PathCmd>, travel: f32, reverse: bool) {
+        match self.circle {
+            Some((center, radius)) => {
+                // `circle_arc` turns positive angles toward the clockwise
+                // perpendicular of the starting direction.
+                let radial = (1.0 / radius) * (self.origin - center);
+                let sign = self.dir.dot(Vec2::new(radial.y, -radial.x)).signum();
+                if reverse {
+                    let from = (1.0 / radius) * (self.point_at(travel) - center);
+                    circle_arc(output, center, radius, from, -sign * travel);
+                } else {
+                    circle_arc(output, center, radius, radial, sign * travel);
+                }
+            }
+            None => {
+                let point = if reverse { self.origin } else { self.point_at(travel) };
+                output.push(PathCmd::Line(point));
+            }
+        }
+    }
+
+    /// Finds the points where two extensions intersect.
+    fn intersections(&self, other: &Extension) -> Vec<Vec2> {
+        match (self.circle, other.circle) {
+            (None, None) => {
+                let denom = self.dir.cross(other.dir);
+                if denom == 0.0 {
+                    return Vec::new();
+                }
+                let travel = (other.origin - self.origin).cross(other.dir) / denom;
+                vec![self.point_at(travel)]
+            }
+            (Some(_), None) => other.intersections(self),
+            (None, Some((center, radius))) => {
+                // Solve |origin + t * dir - center| = radius for t.
+                let offset = self.origin - center;
+                let b = offset.dot(self.dir);
+                let discriminant = b * b - (offset.dot(offset) - radius * radius);
+                if discriminant < 0.0 {
+                    return Vec::new();
+                }
+                let root = discriminant.sqrt();
+                vec![self.point_at(-b - root), self.point_at(-b + root)]
+            }
+            (Some((center0, radius0)), Some((center1, radius1))) => {
+                let between = center1 - center0;
+                let distance = between.length();
+                if distance == 0.0 || distance > radius0 + radius1 || distance < (radius0 - radius1).abs() {
+                    return Vec::new();
+                }
+                let dir = (1.0 / distance) * between;
+                let along = (distance * distance + radius0 * radius0 - radius1 * radius1) / (2.0 * distance);
+                let across = (radius0 * radius0 - along * along).max(0.0).sqrt();
+                let base = center0 + along * dir;
+                let normal = Vec2::new(-dir.y, dir.x);
+                vec![base + across * normal, base - across * normal]
+            }
+        }
+    }
+}
+
+/// Emits the outer side of an `Arcs` join, returning `false` without emitting
+/// anything if the extended edges don't meet.
+fn arcs_join(
+    output: &mut Vec<PathCmd>,
+    style: &StrokeStyle,
+    point: Vec2,
+    prev_normal: Vec2,
+    next_normal: Vec2,
+    prev_curvature: f32,
+    next_curvature: f32,
+) -> bool {
+    let half_width = 0.5 * style.width;
+    let prev_dir = Vec2::new(prev_normal.y, -prev_normal.x);
+    let next_dir = Vec2::new(next_normal.y, -next_normal.x);
+    let start = point + half_width * prev_normal;
+    let end = point + half_width * next_normal;
+    let prev = Extension::new(start, prev_dir, prev_normal, prev_curvature, half_width);
+    let next = Extension::new(end, -1.0 * next_dir, next_normal, next_curvature, half_width);
+
+    // Use the intersection closest to the join point.
+    let mut meet: Option<(Vec2, f32, f32)> = None;
+    for candidate in prev.intersections(&next) {
+        let (prev_travel, next_travel) = (prev.travel(candidate), next.travel(candidate));
+        let closer = match meet {
+            Some((closest, _, _)) => (candidate - point).length() < (closest - point).length(),
+            None => true,
+        };
+        if closer && prev.is_valid(prev_travel) && next.is_valid(next_travel) {
+            meet = Some((candidate, prev_travel, next_travel));
+        }
+    }
+    let (meet, prev_travel, next_travel) = match meet {
+        Some(meet) => meet,
+        None => return false,
+    };
+
+    output.push(PathCmd::Line(start));
+    let limit = style.miter_limit * half_width;
+    let extent = (meet - point).length();
+    if extent <= limit {
+        prev.draw(output, prev_travel, false);
+        next.draw(output, next_travel, true);
+        return true;
+    }
+
+    // Clip the join along the line perpendicular to the direction of the
+    // meeting point, at the limit distance from the join point.
+    let axis = (1.0 / extent) * (meet - point);
+    let distance = limit + axis.dot(point);
+    if axis.dot(start) >= distance || axis.dot(end) >= distance {
+        output.push(PathCmd::Line(end));
+        return true;
+    }
+    let prev_clip = prev.travel_to_line(axis, distance).unwrap_or(prev_travel).min(prev_travel);
+    let next_clip = next.travel_to_line(axis, distance).unwrap_or(next_travel).min(next_travel);
+    prev.draw(output, prev_clip, false);
+    output.push(PathCmd::Line(next.point_at(next_clip)));
+    next.draw(output, next_clip, true);
+    true
+}
+
+/// Caps the end of an offset segment with the given normal, going from the
+/// offset point on the left side of `point` to the one on the right side.
+#[inline]
+fn cap(output: &mut Vec<PathCmd>, style: &StrokeStyle, normal: Vec2, point: Vec2) {
+    let offset = 0.5 * style.width * normal;
+    let forward = Vec2::new(offset.y, -offset.x);
+    match style.cap {
+        LineCap::Butt => {
+            output.push(PathCmd::Line(point - offset));
+        }
+        LineCap::Round => {
+            let weight = std::f32::consts::FRAC_1_SQRT_2;
+            output.push(PathCmd::Conic(point + offset + forward, point + forward, weight));
+            output.push(PathCmd::Conic(point - offset + forward, point - offset, weight));
+        }
+        LineCap::Square => {
+            output.push(PathCmd::Line(point + offset + forward));
+            output.push(PathCmd::Line(point - offset + forward));
+            output.push(PathCmd::Line(point - offset));
+        }
+    }
+}
+
+/// Offsets one side of a contour, emitting its joins but not its endpoints.
+fn offset(output: &mut Vec<PathCmd>, style: &StrokeStyle, points: &[Vec2], closed: bool) {
+    let len = points.len();
+    let (start, end) = if closed { (0, len) } else { (1, len - 1) };
+    for i in start..end {
+        let prev = points[(i + len - 1) % len];
+        let point = points[i];
+        let next = points[(i + 1) % len];
+        let prev_length = (point - prev).length();
+        let next_length = (next - point).length();
+        join(output, style, point, normal(prev, point), normal(point, next), prev_length, next_length, 0.0, 0.0);
+    }
+}
+
+fn stroke_contour(output: &mut Vec<PathCmd>, style: &StrokeStyle, points: &mut Vec<Vec2>, closed: bool) {
+    let half_width = 0.5 * style.width;
+
+    if closed && points.len() > 1 && points[0] == points[points.len() - 1] {
+        points.pop();
+    }
+
+    if points.len() == 1 {
+        // Zero-length subpaths are drawn as just their caps, oriented along
+        // the x axis.
+        let point = points[0];
+        match style.cap {
+            LineCap::Butt => {}
+            LineCap::Round | LineCap::Square => {
+                output.push(PathCmd::Move(point + Vec2::new(0.0, half_width)));
+                cap(output, style, Vec2::new(0.0, 1.0), point);
+                cap(output, style, Vec2::new(0.0, -1.0), point);
+                output.push(PathCmd::Close);
+            }
+        }
+    } else if closed {
+        for _ in 0..2 {
+            let base = output.len();
+            offset(output, style, points, true);
+            output[base] = PathCmd::Move(match output[base] {
+                PathCmd::Line(point) => point,
+                _ => unreachable!(),
+            });
+            output.push(PathCmd::Close);
+            points.reverse();
+        }
+    } else {
+        output.push(PathCmd::Move(points[0] + half_width * normal(points[0], points[1])));
+        for _ in 0..2 {
+            let len = points.len();
+            offset(output, style, points, false);
+            let end_normal = normal(points[len - 2], points[len - 1]);
+            output.push(PathCmd::Line(points[len - 1] + half_width * end_normal));
+            cap(output, style, end_normal, points[len - 1]);
+            points.reverse();
+        }
+        output.push(PathCmd::Close);
+    }
+
+    points.clear();
+}
+
+/// Converts the given path to a stroked path with the given style.
+///
+/// This function will panic if the given path is not piecewise-linear (i.e. if
+/// it contains [`PathCmd`]s other than `Move`, `Line`, or `Close`. Paths
+/// containing curves can be stroked with [`stroke_curves`].
+///
+/// As in SVG, subpaths of zero length are drawn as dots or squares when using
+/// round or square caps. If the style has a dash array, the path is first split
+/// into dashes using [`dash`].
+///
+/// [`dash`]: crate::dash
+/// [`stroke_curves`]: crate::stroke_curves
+pub fn stroke(polygon: &[PathCmd], style: &StrokeStyle) -> Vec<PathCmd> {
+    let dashed;
+    let polygon = if style.dashes.is_empty() {
+        polygon
+    } else {
+        dashed = dash(polygon, &style.dashes, style.dash_offset);
+        &dashed
+    };
+
+    let mut output = Vec::new();
+
+    let mut points = Vec::new();
+    let mut drawn = false;
+    for command in polygon {
+        match *command {
+            PathCmd::Move(point) => {
+                if drawn {
+                    stroke_contour(&mut output, style, &mut points, false);
+                }
+                points.clear();
+                points.push(point);
+                drawn = false;
+            }
+            PathCmd::Line(point) => {
+                if points.last() != Some(&point) {
+                    points.push(point);
+                }
+                drawn = true;
+            }
+            PathCmd::Close => {
+                if let Some(&start) = points.first() {
+                    stroke_contour(&mut output, style, &mut points, true);
+                    points.push(start);
+                }
+                drawn = false;
+            }
+            _ => {
+                panic!();
+            }
+        }
+    }
+    if drawn {
+        stroke_contour(&mut output, style, &mut points, false);
+    }
+
+    output
+}
+
+/// Evaluates the position and the first and second derivatives of the segment
+/// starting at `last` at the parameter `t`.
+fn derivatives(last: Vec2, command: PathCmd, t: f32) -> (Vec2, Vec2, Vec2) {
+    match Segment::new(last, command) {
+        Some(segment) => segment.derivatives(t),
+        None => (last, Vec2::new(0.0, 0.0), Vec2::new(0.0, 0.0)),
+    }
+}
+
+/// Returns the unit normal of the segment starting at `last` at the parameter
+/// `t`, falling back to higher derivatives and then to the chord where the
+/// first derivative vanishes.
+pub(crate) fn curve_normal(last: Vec2, command: PathCmd, t: f32) -> Vec2 {
+    let (_, first, second) = derivatives(last, command, t);
+    let end = end_point(command).unwrap_or(last);
+    let tangent = if first.length() > 0.0 {
+        first
+    } else if second.length() > 0.0 {
+        // At an endpoint, the tangent points toward the interior of the curve.
+        if t < 0.5 {
+            second
+        } else {
+            -1.0 * second
+        }
+    } else {
+        end - last
+    };
+    normal(Vec2::new(0.0, 0.0), tangent)
+}
+
+/// Returns the signed curvature of the segment starting at `last` at the
+/// parameter `t`, which is positive where it turns toward its left normal.
+fn curvature(last: Vec2, command: PathCmd, t: f32) -> f32 {
+    let (_, first, second) = derivatives(last, command, t);
+    let speed = first.length();
+    if speed > 0.0 {
+        first.cross(second) / (speed * speed * speed)
+    } else {
+        0.0
+    }
+}
+
+/// Returns the point and derivative of the curve offset by `distance` along
+/// the left normal of the segment starting at `last`, at the parameter `t`.
+fn offset_point(last: Vec2, command: PathCmd, distance: f32, t: f32) -> (Vec2, Vec2) {
+    let (position, first, second) = derivatives(last, command, t);
+    let normal = curve_normal(last, command, t);
+    // The normal turns at a rate of the curvature times the speed, so the
+    // offset curve moves at a rate of (1 - distance * curvature) times the
+    // speed of the original.
+    let speed = first.length();
+    let derivative = if speed > 0.0 {
+        let curvature = first.cross(second) / (speed * speed * speed);
+        (1.0 - distance * curvature) * first
+    } else {
+        Vec2::new(0.0, 0.0)
+    };
+    (position + distance * normal, derivative)
+}
+
+/// Approximates the curve offset by `distance` from the segment starting at
+/// `last` between the parameters `t0` and `t1` with curves of the same type,
+/// subdividing until the approximation is within the given tolerance.
+#[allow(clippy::too_many_arguments)]
+fn offset_curve(
+    output: &mut Vec<PathCmd>,
+    last: Vec2,
+    command: PathCmd,
+    distance: f32,
+    tolerance: f32,
+    t0: f32,
+    t1: f32,
+    depth: u32,
+) {
+    let (start, start_derivative) = offset_point(last, command, distance, t0);
+    let (end, end_derivative) = offset_point(last, command, distance, t1);
+
+    let approximation = match command {
+        PathCmd::Cubic(..) => {
+            // Match the derivatives of the offset curve at both endpoints.
+            let scale = (t1 - t0) / 3.0;
+            PathCmd::Cubic(start + scale * start_derivative, end - scale * end_derivative, end)
+        }
+        PathCmd::Quadratic(..) | PathCmd::Conic(..) => {
+            // Place the control point at the intersection of the offset
+            // curve's tangents at its endpoints, and keep the weight of the
+            // original segment, which makes offsets of circular arcs exact.
+            let start_tangent = derivatives(last, command, t0).1;
+            let end_tangent = derivatives(last, command, t1).1;
+            let denominator = start_tangent.cross(end_tangent);
+            let control = if denominator != 0.0 {
+                start + ((end - start).cross(end_tangent) / denominator) * start_tangent
+            } else {
+                Vec2::lerp(0.5, start, end)
+            };
+            match subsegment(last, command, t0, t1) {
+                PathCmd::Conic(_, _, weight) => PathCmd::Conic(control, end, weight),
+                _ => PathCmd::Quadratic(control, end),
+            }
+        }
+        _ => PathCmd::Line(end),
+    };
+
+    if let PathCmd::Line(_) = approximation {
+        output.push(approximation);
+        return;
+    }
+
+    // Measure the error along the normal at a few points, since the
+    // approximation need not match the parametrization of the offset curve.
+    let within_tolerance = [0.25, 0.5, 0.75].iter().all(|&s| {
+        let t = t0 + s * (t1 - t0);
+        let (expected, _) = offset_point(last, command, distance, t);
+        let actual = point_at(start, approximation, s);
+        let normal = curve_normal(last, command, t);
+        let error = actual - expected;
+        error.dot(normal).abs() <= tolerance && error.length() <= 0.5 * (end - start).length() + tolerance
+    });
+
+    if within_tolerance || depth >= 12 {
+        output.push(approximation);
+    } else {
+        let t = 0.5 * (t0 + t1);
+        offset_curve(output, last, command, distance, tolerance, t0, t, depth + 1);
+        offset_curve(output, last, command, distance, tolerance, t, t1, depth + 1);
+    }
+}
+
+/// Returns the given segment traversed in the opposite direction, ending at
+/// `last`.
+fn reverse_segment(last: Vec2, command: PathCmd) -> PathCmd {
+    match command {
+        PathCmd::Line(_) => PathCmd::Line(last),
+        PathCmd::Quadratic(control, _) => PathCmd::Quadratic(control, last),
+        PathCmd::Cubic(control1, control2, _) => PathCmd::Cubic(control2, control1, last),
+        PathCmd::Conic(control, _, weight) => PathCmd::Conic(control, last, weight),
+        _ => command,
+    }
+}
+
+/// Offsets one side of a contour given as a list of segments and their start
+/// points, emitting its joins and its offset curves. For closed contours, this
+/// begins with the join at the start of the contour.
+fn offset_curves(
+    output: &mut Vec<PathCmd>,
+    style: &StrokeStyle,
+    segments: &[(Vec2, PathCmd)],
+    closed: bool,
+    tolerance: f32,
+) {
+    let half_width = 0.5 * style.width;
+    for (i, &(last, command)) in segments.iter().enumerate() {
+        if i > 0 || closed {
+            let (prev_last, prev_command) = segments[(i + segments.len() - 1) % segments.len()];
+            let prev_normal = curve_normal(prev_last, prev_command, 1.0);
+            let next_normal = curve_normal(last, command, 0.0);
+            if prev_normal != next_normal {
+                // The inner side of a join between curves is always routed
+                // through the join point, since the curves may not intersect.
+                let (prev_length, next_length) = match (prev_command, command) {
+                    (PathCmd::Line(_), PathCmd::Line(next)) => {
+                        ((last - prev_last).length(), (next - last).length())
+                    }
+                    _ => (0.0, 0.0),
+                };
+                let prev_curvature = curvature(prev_last, prev_command, 1.0);
+                let next_curvature = curvature(last, command, 0.0);
+                let base = output.len();
+                join(
+                    output,
+                    style,
+                    last,
+                    prev_normal,
+                    next_normal,
+                    prev_length,
+                    next_length,
+                    prev_curvature,
+                    next_curvature,
+                );
+                // Drop the lines back to the end of the previous offset curve.
+                let mut current = end_point(output[base - 1]);
+                let mut i = base;
+                while i < output.len() {
+                    if let PathCmd::Line(point) = output[i] {
+                        if Some(point) == current {
+                            output.remove(i);
+                            continue;
+                        }
+                    }
+                    current = end_point(output[i]);
+                    i += 1;
+                }
+                let start = last + half_width * next_normal;
+                if current != Some(start) {
+                    output.push(PathCmd::Line(start));
+                }
+            }
+        }
+        offset_curve(output, last, command, half_width, tolerance, 0.0, 1.0, 0);
+    }
+}
+
+fn stroke_curve_contour(
+    output: &mut Vec<PathCmd>,
+    style: &StrokeStyle,
+    start: Vec2,
+    segments: &mut Vec<(Vec2, PathCmd)>,
+    closed: bool,
+    tolerance: f32,
+) {
+    let half_width = 0.5 * style.width;
+
+    if segments.is_empty() {
+        stroke_contour(output, style, &mut vec![start], closed);
+        return;
+    }
+
+    for side in 0..2 {
+        let (first_last, first_command) = segments[0];
+        if closed {
+            let (last, command) = segments[segments.len() - 1];
+            output.push(PathCmd::Move(
+                first_last + half_width * curve_normal(last, command, 1.0),
+            ));
+        } else if side == 0 {
+            output.push(PathCmd::Move(
+                first_last + half_width * curve_normal(first_last, first_command, 0.0),
+            ));
+        }
+
+        offset_curves(output, style, segments, closed, tolerance);
+
+        if closed {
+            output.push(PathCmd::Close);
+        } else {
+            let (last, command) = segments[segments.len() - 1];
+            let end = end_point(command).unwrap();
+            cap(output, style, curve_normal(last, command, 1.0), end);
+        }
+
+        let reversed = segments
+            .iter()
+            .rev()
+            .map(|&(last, command)| (end_point(command).unwrap(), reverse_segment(last, command)))
+            .collect();
+        *segments = reversed;
+    }
+
+    if !closed {
+        output.push(PathCmd::Close);
+    }
+
+    segments.clear();
+}
+
+/// Converts the given path to a stroked path with the given style, preserving
+/// curves.
+///
+/// Unlike [`stroke`], this function accepts paths containing curves and
+/// offsets them directly, approximating the edges of the stroke with curves of
+/// the same type to within the given tolerance: quadratics become quadratics,
+/// cubics become cubics and conics become conics. This produces smaller and
+/// more accurate outlines, which is useful for exporting them or for filling
+/// them at large scales.
+///
+/// Where a curve bends more tightly than half the stroke width, its inner edge
+/// loops back on itself, so the output should be filled with the nonzero fill
+/// rule.
+///
+/// [`stroke`]: crate::stroke
+pub fn stroke_curves(path: &[PathCmd], style: &StrokeStyle, tolerance: f32) -> Vec<PathCmd> {
+    let dashed;
+    let path = if style.dashes.is_empty() {
+        path
+    } else {
+        dashed = dash(path, &style.dashes, style.dash_offset);
+        &dashed
+    };
+
+    let mut output = Vec::new();
+
+    let mut segments = Vec::new();
+    let mut start = Vec2::new(0.0, 0.0);
+    let mut last = Vec2::new(0.0, 0.0);
+    let mut drawn = false;
+    for &command in path {
+        match command {
+            PathCmd::Move(point) => {
+                if drawn {
+                    stroke_curve_contour(&mut output, style, start, &mut segments, false, tolerance);
+                }
+                start = point;
+                last = point;
+                drawn = false;
+            }
+            PathCmd::Close => {
+                if last != start {
+                    segments.push((last, PathCmd::Line(start)));
+                }
+                stroke_curve_contour(&mut output, style, start, &mut segments, true, tolerance);
+                last = start;
+                drawn = false;
+            }
+            _ => {
+                let point = end_point(command).unwrap();
+                let degenerate = match command {
+                    PathCmd::Quadratic(control, _) | PathCmd::Conic(control, _, _) => {
+                        control == last && point == last
+                    }
+                    PathCmd::Cubic(control1, control2, _) => {
+                        control1 == last && control2 == last && point == last
+                    }
+                    _ => point == last,
+                };
+                if !degenerate {
+                    segments.push((last, command));
+                }
+                last = point;
+                drawn = true;
+            }
+        }
+    }
+    if drawn {
+        stroke_curve_contour(&mut output, style, start, &mut segments, false, tolerance);
+    }
+
+    output
+}
+
+/// Appends an arc of the circle with the given center and radius, starting in
+/// the direction `from` and turning by `angle` in the same direction as round
+/// caps, split into pieces spanning at most 90 degrees.
+fn circle_arc(output: &mut Vec<PathCmd>, center: Vec2, radius: f32, from: Vec2, angle: f32) {
+    let pieces = (angle.abs() / std::f32::consts::FRAC_PI_2 - 1e-3).ceil().max(1.0) as u32;
+    let step = angle / pieces as f32;
+    let weight = (0.5 * step).cos();
+    let forward = Vec2::new(from.y, -from.x);
+    let mut prev = from;
+    for i in 1..=pieces {
+        let (sin, cos) = (i as f32 * step).sin_cos();
+        let next = cos * from + sin * forward;
+        let control = center + (radius / (1.0 + prev.dot(next))) * (prev + next);
+        output.push(PathCmd::Conic(control, center + radius * next, weight));
+        prev = next;
+    }
+}
+
+/// Returns the directions from the centers of two circles to the points where
+/// the tangent lines on the left and right of the segment between them touch
+/// them. If one circle contains the other, both directions point from the
+/// larger circle's center toward the smaller one's.
+fn tangent_directions(center0: Vec2, radius0: f32, center1: Vec2, radius1: f32) -> (Vec2, Vec2) {
+    let length = (center1 - center0).length();
+    let dir = (1.0 / length) * (center1 - center0);
+    let normal = Vec2::new(-dir.y, dir.x);
+    // The cosine of the angle between the segment and these directions.
+    let cos = ((radius0 - radius1) / length).clamp(-1.0, 1.0);
+    let sin = (1.0 - cos * cos).sqrt();
+    (cos * dir + sin * normal, cos * dir - sin * normal)
+}
+
+/// Offsets the left side of a polyline with a radius at each point, ending at
+/// the last point. For closed polylines, this begins with the join at the
+/// first point.
+fn offset_variable(output: &mut Vec<PathCmd>, points: &[(Vec2, f32)], closed: bool) {
+    let len = points.len();
+    let segments = if closed { len } else { len - 1 };
+    let left = |i: usize| {
+        let (point0, radius0) = points[i % len];
+        let (point1, radius1) = points[(i + 1) % len];
+        tangent_directions(point0, radius0, point1, radius1).0
+    };
+
+    for i in 0..segments {
+        if i > 0 || closed {
+            // Round the join if the tangent points are separated by a turn of
+            // at most 180 degrees around the circle, and otherwise connect
+            // them through its center.
+            let (point, radius) = points[i];
+            let from = left(i + len - 1);
+            let to = left(i);
+            let forward = Vec2::new(from.y, -from.x);
+            let angle = to.dot(forward).atan2(to.dot(from));
+            if angle >= 0.0 {
+                circle_arc(output, point, radius, from, angle);
+            } else {
+                output.push(PathCmd::Line(point));
+                output.push(PathCmd::Line(point + radius * to));
+            }
+        }
+        let (point, radius) = points[(i + 1) % len];
+        output.push(PathCmd::Line(point + radius * left(i)));
+    }
+}
+
+/// Appends the outline of a polyline with a radius at each point.
+fn stroke_variable_contour(output: &mut Vec<PathCmd>, points: &mut [(Vec2, f32)], closed: bool) {
+    use std::f32::consts::PI;
+
+    for point in points.iter_mut() {
+        point.1 = 0.5 * point.1.max(0.0);
+    }
+
+    if points.len() == 1 {
+        let (point, radius) = points[0];
+        if radius > 0.0 {
+            output.push(PathCmd::Move(point + Vec2::new(0.0, radius)));
+            circle_arc(output, point, radius, Vec2::new(0.0, 1.0), 2.0 * PI);
+            output.push(PathCmd::Close);
+        }
+    } else if closed {
+        for _ in 0..2 {
+            let len = points.len();
+            let (point, radius) = points[0];
+            let (prev, prev_radius) = points[len - 1];
+            let from = tangent_directions(prev, prev_radius, point, radius).0;
+            output.push(PathCmd::Move(point + radius * from));
+            offset_variable(output, points, true);
+            output.push(PathCmd::Close);
+            points.reverse();
+        }
+    } else {
+        let (point, radius) = points[0];
+        let (next, next_radius) = points[1];
+        output.push(PathCmd::Move(point + radius * tangent_directions(point, radius, next, next_radius).0));
+        for _ in 0..2 {
+            offset_variable(output, points, false);
+            // Cap the end with the part of its circle beyond the tangent
+            // points.
+            let len = points.len();
+            let (prev, prev_radius) = points[len - 2];
+            let (point, radius) = points[len - 1];
+            let (left, right) = tangent_directions(prev, prev_radius, point, radius);
+            let forward = Vec2::new(left.y, -left.x);
+            let angle = right.dot(forward).atan2(right.dot(left)).rem_euclid(2.0 * PI);
+            circle_arc(output, point, radius, left, angle);
+            points.reverse();
+        }
+        output.push(PathCmd::Close);
+    }
+}
+
+/// Flattens the given path and computes the widths of the resulting points,
+/// given the width at the start of each command and the width at its end.
+fn variable_contours(
+    path: &[PathCmd],
+    tolerance: f32,
+    mut widths: impl FnMut(usize) -> (f32, f32),
+    mut contour: impl FnMut(&mut Vec<(Vec2, f32)>, bool),
+) {
+    let mut points: Vec<(Vec2, f32)> = Vec::new();
+    let mut start = Vec2::new(0.0, 0.0);
+    let mut last = Vec2::new(0.0, 0.0);
+    let mut drawn = false;
+    for (i, &command) in path.iter().enumerate() {
+        match command {
+            PathCmd::Move(point) => {
+                if drawn {
+                    contour(&mut points, false);
+                }
+                points.clear();
+                start = point;
+                last = point;
+                drawn = false;
+            }
+            PathCmd::Close => {
+                if points.is_empty() {
+                    points.push((start, widths(i).0));
+                }
+                if points.len() > 1 && points[0].0 == points[points.len() - 1].0 {
+                    points.pop();
+                }
+                contour(&mut points, true);
+                points.clear();
+                last = start;
+                drawn = false;
+            }
+            _ => {
+                let (start_width, end_width) = widths(i);
+                if points.is_empty() {
+                    points.push((last, start_width));
+                }
+
+                // Interpolate the width along the length of the flattened
+                // command.
+                let base = points.len();
+                let mut length = 0.0;
+                let mut prev = last;
+                command.flatten(last, tolerance, |cmd| {
+                    if let PathCmd::Line(point) = cmd {
+                        if point != prev {
+                            length += (point - prev).length();
+                            points.push((point, length));
+                            prev = point;
+                        }
+                    }
+                });
+                for point in &mut points[base..] {
+                    let t = if length > 0.0 { point.1 / length } else { 1.0 };
+                    point.1 = start_width + t * (end_width - start_width);
+                }
+
+                last = end_point(command).unwrap();
+                drawn = true;
+            }
+        }
+    }
+    if drawn {
+        contour(&mut points, false);
+    }
+}
+
+/// Converts the given path to the outline of a stroke whose width varies along
+/// each subpath, as given by a function of the fraction of the subpath's
+/// length traveled (from 0 at its start to 1 at its end).
+///
+/// The path is flattened to within the given tolerance, and the outline is
+/// given round joins and caps. As with [`stroke`], the outline may overlap
+/// itself on the inner side of joins, so it should be filled with the nonzero
+/// fill rule (as with [`Rasterizer::fill`]).
+///
+/// ```
+/// use ochre::{stroke_variable, PathCmd, Vec2};
+///
+/// // A stroke tapering to a point at both ends.
+/// let path = [PathCmd::Move(Vec2::new(10.0, 10.0)), PathCmd::Line(Vec2::new(90.0, 10.0))];
+/// let outline = stroke_variable(&path, |t| 8.0 * (1.0 - (2.0 * t - 1.0).abs()), 0.1);
+/// assert!(!outline.is_empty());
+/// ```
+///
+/// [`stroke`]: crate::stroke
+/// [`Rasterizer::fill`]: crate::Rasterizer::fill
+pub fn stroke_variable(path: &[PathCmd], width: impl Fn(f32) -> f32, tolerance: f32) -> Vec<PathCmd> {
+    let mut output = Vec::new();
+    variable_contours(path, tolerance, |_| (0.0, 0.0), |points, closed| {
+        // Replace the placeholder widths with the width function evaluated at
+        // each point's fraction of the subpath's length.
+        let mut lengths = Vec::with_capacity(points.len() + 1);
+        let mut length = 0.0;
+        lengths.push(0.0);
+        for i in 1..points.len() {
+            length += (points[i].0 - points[i - 1].0).length();
+            lengths.push(length);
+        }
+        if closed && points.len() > 1 {
+            length += (points[0].0 - points[points.len() - 1].0).length();
+        }
+        for (point, position) in points.iter_mut().zip(lengths) {
+            point.1 = width(if length > 0.0 { position / length } else { 0.0 });
+        }
+        stroke_variable_contour(&mut output, points, closed);
+    });
+    output
+}
+
+/// Converts the given path to the outline of a stroke whose width varies along
+/// each subpath, as given by the width at the end point of each command.
+///
+/// `widths` must have one entry for each command in `path`. The width is
+/// interpolated linearly along the length of each command, from the width at
+/// the end of the previous command to the width at its own end. Since a
+/// closing segment ends at the start of its subpath, entries for `Close`
+/// commands are not used. This is suitable for strokes with a pressure value
+/// recorded at each point.
+///
+/// The output is as described for [`stroke_variable`].
+///
+/// [`stroke_variable`]: crate::stroke_variable
+pub fn stroke_variable_points(path: &[PathCmd], widths: &[f32], tolerance: f32) -> Vec<PathCmd> {
+    assert_eq!(path.len(), widths.len(), "path and widths must have the same length");
+
+    // Find the width at the start of each command, which for a closing segment
+    // is the width at the start of the subpath.
+    let mut start_widths = Vec::with_capacity(path.len());
+    let mut start_width = 0.0;
+    let mut last_width = 0.0;
+    for (command, &width) in path.iter().zip(widths) {
+        match command {
+            PathCmd::Move(_) => {
+                start_widths.push(last_width);
+                start_width = width;
+                last_width = width;
+            }
+            PathCmd::Close => {
+                start_widths.push(start_width);
+                last_width = start_width;
+            }
+            _ => {
+                start_widths.push(last_width);
+                last_width = width;
+            }
+        }
+    }
+
+    let mut output = Vec::new();
+    variable_contours(
+        path,
+        tolerance,
+        |i| match path[i] {
+            PathCmd::Close => (start_widths[i], start_widths[i]),
+            _ => (start_widths[i], widths[i]),
+        },
+        |points, closed| stroke_variable_contour(&mut output, points, closed),
+    );
     output
 }
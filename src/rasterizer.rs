@@ -1,9 +1,21 @@
-use crate::{flatten, stroke, PathCmd, Transform, Vec2};
+use std::convert::TryFrom;
+
+use crate::{stroke_curves, PathCmd, StrokeStyle, TileList, Transform, Vec2};
 
 /// The tile size used by the rasterizer (not configurable).
 pub const TILE_SIZE: usize = 8;
 
-const TOLERANCE: f32 = 0.1;
+pub(crate) const TOLERANCE: f32 = 0.1;
+
+/// Number of fractional bits in the fixed-point area and height values of an
+/// [`Increment`].
+const INCREMENT_BITS: u32 = 14;
+const INCREMENT_ONE: f32 = (1 << INCREMENT_BITS) as f32;
+
+/// Number of fractional bits in the coordinates used by fixed-point
+/// rasterization (see [`Rasterizer::fixed_point`]).
+const FIXED_BITS: u32 = 8;
+const FIXED_ONE: f32 = (1 << FIXED_BITS) as f32;
 
 /// A trait to implement for consuming the tile data produced by a
 /// [`Rasterizer`].
@@ -21,12 +33,17 @@ pub trait TileBuilder {
     fn span(&mut self, x: i16, y: i16, width: u16);
 }
 
+/// The contribution of a path to the coverage of a single pixel.
+///
+/// Area and height are stored in fixed point with [`INCREMENT_BITS`]
+/// fractional bits. Heights are computed as differences of quantized
+/// pixel-local coordinates, so that they sum exactly across a row.
 #[derive(Copy, Clone)]
 struct Increment {
     x: i16,
     y: i16,
-    area: f32,
-    height: f32,
+    area: i16,
+    height: i16,
 }
 
 #[derive(Copy, Clone)]
@@ -36,6 +53,150 @@ struct TileIncrement {
     sign: i8,
 }
 
+/// Sorts items by `(tile_y, tile_x)`.
+///
+/// Since tile coordinates fall within the small range spanned by the path's
+/// bounding box, this is done as a stable two-pass counting sort (first by
+/// column, then by row) rather than a comparison sort.
+fn sort_by_tile<T: Copy>(items: &mut Vec<T>, key: impl Fn(&T) -> (i16, i16)) {
+    if items.len() < 2 {
+        return;
+    }
+
+    let mut min = (i16::MAX, i16::MAX);
+    let mut max = (i16::MIN, i16::MIN);
+    for item in items.iter() {
+        let (tile_x, tile_y) = key(item);
+        min = (min.0.min(tile_x), min.1.min(tile_y));
+        max = (max.0.max(tile_x), max.1.max(tile_y));
+    }
+
+    let mut counts = Vec::new();
+    let mut sorted = Vec::new();
+    counting_sort(items, &mut sorted, &mut counts, min.0, max.0, |item| key(item).0);
+    counting_sort(&sorted, items, &mut counts, min.1, max.1, |item| key(item).1);
+}
+
+/// Stably sorts `src` into `dst` by a key lying in the range `min..=max`.
+fn counting_sort<T: Copy>(
+    src: &[T],
+    dst: &mut Vec<T>,
+    counts: &mut Vec<usize>,
+    min: i16,
+    max: i16,
+    key: impl Fn(&T) -> i16,
+) {
+    counts.clear();
+    counts.resize((max as i32 - min as i32) as usize + 2, 0);
+    for item in src {
+        counts[(key(item) as i32 - min as i32) as usize + 1] += 1;
+    }
+    for i in 1..counts.len() {
+        counts[i] += counts[i - 1];
+    }
+
+    dst.clear();
+    dst.resize(src.len(), src[0]);
+    for item in src {
+        let bucket = &mut counts[(key(item) as i32 - min as i32) as usize];
+        dst[*bucket] = *item;
+        *bucket += 1;
+    }
+}
+
+/// Converts the accumulated areas and heights of a tile into coverage values.
+///
+/// `prev` holds the accumulated heights carried in from the left edge of the
+/// tile, and the accumulated heights at its right edge are written to `next`.
+#[inline]
+fn accumulate_tile(
+    prev: &[i32; TILE_SIZE],
+    areas: &[i32; TILE_SIZE * TILE_SIZE],
+    heights: &[i32; TILE_SIZE * TILE_SIZE],
+    next: &mut [i32; TILE_SIZE],
+) -> [u8; TILE_SIZE * TILE_SIZE] {
+    #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+    {
+        if is_x86_feature_detected!("sse2") {
+            return unsafe { accumulate_tile_sse2(prev, areas, heights, next) };
+        }
+    }
+
+    accumulate_tile_scalar(prev, areas, heights, next)
+}
+
+fn accumulate_tile_scalar(
+    prev: &[i32; TILE_SIZE],
+    areas: &[i32; TILE_SIZE * TILE_SIZE],
+    heights: &[i32; TILE_SIZE * TILE_SIZE],
+    next: &mut [i32; TILE_SIZE],
+) -> [u8; TILE_SIZE * TILE_SIZE] {
+    let mut tile = [0; TILE_SIZE * TILE_SIZE];
+    for y in 0..TILE_SIZE {
+        let mut accum = prev[y];
+        for x in 0..TILE_SIZE {
+            tile[y * TILE_SIZE + x] = ((accum + areas[y * TILE_SIZE + x]).abs() >> (INCREMENT_BITS - 8)).min(255) as u8;
+            accum += heights[y * TILE_SIZE + x];
+        }
+        next[y] = accum;
+    }
+    tile
+}
+
+/// SSE2 version of [`accumulate_tile_scalar`], producing identical output.
+///
+/// Each row is handled as two vectors of four lanes. The running sum over
+/// `heights` is computed as a log-step prefix sum, and the clamp to 255 falls
+/// out of the saturating pack instructions.
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+#[target_feature(enable = "sse2")]
+unsafe fn accumulate_tile_sse2(
+    prev: &[i32; TILE_SIZE],
+    areas: &[i32; TILE_SIZE * TILE_SIZE],
+    heights: &[i32; TILE_SIZE * TILE_SIZE],
+    next: &mut [i32; TILE_SIZE],
+) -> [u8; TILE_SIZE * TILE_SIZE] {
+    #[cfg(target_arch = "x86")]
+    use std::arch::x86::*;
+    #[cfg(target_arch = "x86_64")]
+    use std::arch::x86_64::*;
+
+    #[inline(always)]
+    unsafe fn prefix_sum(v: __m128i) -> __m128i {
+        let v = _mm_add_epi32(v, _mm_slli_si128(v, 4));
+        _mm_add_epi32(v, _mm_slli_si128(v, 8))
+    }
+
+    #[inline(always)]
+    unsafe fn abs(v: __m128i) -> __m128i {
+        let sign = _mm_srai_epi32(v, 31);
+        _mm_sub_epi32(_mm_xor_si128(v, sign), sign)
+    }
+
+    let mut tile = [0; TILE_SIZE * TILE_SIZE];
+    for y in 0..TILE_SIZE {
+        let row = y * TILE_SIZE;
+        let heights_lo = _mm_loadu_si128(heights[row..].as_ptr() as *const __m128i);
+        let heights_hi = _mm_loadu_si128(heights[row + 4..].as_ptr() as *const __m128i);
+        let areas_lo = _mm_loadu_si128(areas[row..].as_ptr() as *const __m128i);
+        let areas_hi = _mm_loadu_si128(areas[row + 4..].as_ptr() as *const __m128i);
+
+        let accum_lo = _mm_add_epi32(_mm_set1_epi32(prev[y]), prefix_sum(heights_lo));
+        let accum_hi = _mm_add_epi32(_mm_shuffle_epi32(accum_lo, 0xFF), prefix_sum(heights_hi));
+
+        let coverage_lo = abs(_mm_add_epi32(_mm_sub_epi32(accum_lo, heights_lo), areas_lo));
+        let coverage_hi = abs(_mm_add_epi32(_mm_sub_epi32(accum_hi, heights_hi), areas_hi));
+        let coverage = _mm_packs_epi32(
+            _mm_srli_epi32(coverage_lo, (INCREMENT_BITS - 8) as i32),
+            _mm_srli_epi32(coverage_hi, (INCREMENT_BITS - 8) as i32),
+        );
+        _mm_storel_epi64(tile[row..].as_mut_ptr() as *mut __m128i, _mm_packus_epi16(coverage, coverage));
+
+        next[y] = _mm_cvtsi128_si32(_mm_shuffle_epi32(accum_hi, 0xFF));
+    }
+    tile
+}
+
 /// Rasterizes paths.
 pub struct Rasterizer {
     increments: Vec<Increment>,
@@ -43,6 +204,17 @@ pub struct Rasterizer {
     first: Vec2,
     last: Vec2,
     tile_y_prev: i16,
+    fixed_point: bool,
+    /// Factor by which the coverage of added line segments is scaled (less
+    /// than 1 only while adding hairlines).
+    coverage: f32,
+    clip: Option<TileList>,
+}
+
+impl Default for Rasterizer {
+    fn default() -> Rasterizer {
+        Rasterizer::new()
+    }
 }
 
 impl Rasterizer {
@@ -54,6 +226,35 @@ impl Rasterizer {
             first: Vec2::new(0.0, 0.0),
             last: Vec2::new(0.0, 0.0),
             tile_y_prev: 0,
+            fixed_point: false,
+            coverage: 1.0,
+            clip: None,
+        }
+    }
+
+    /// Initializes a new rasterizer which uses fixed-point arithmetic.
+    ///
+    /// Line segment endpoints are snapped to 1/256 of a pixel (i.e. 24.8 fixed
+    /// point), after which rasterization uses only integer arithmetic. Before
+    /// snapping, transforming and flattening paths only involves correctly
+    /// rounded `f32` operations, so given the same input, the output of
+    /// [`fill`], [`hairline`] and [`clip`] is bit-identical on every target.
+    ///
+    /// The same holds for [`stroke`] and [`stroke_device_space`], except with
+    /// [`LineJoin::Arcs`] joins, which are computed using trigonometric
+    /// functions from the platform's math library. Their results can differ
+    /// slightly between targets.
+    ///
+    /// [`fill`]: Rasterizer::fill
+    /// [`hairline`]: Rasterizer::hairline
+    /// [`clip`]: Rasterizer::clip
+    /// [`stroke`]: Rasterizer::stroke
+    /// [`stroke_device_space`]: Rasterizer::stroke_device_space
+    /// [`LineJoin::Arcs`]: crate::LineJoin::Arcs
+    pub fn fixed_point() -> Rasterizer {
+        Rasterizer {
+            fixed_point: true,
+            ..Rasterizer::new()
         }
     }
 
@@ -71,72 +272,215 @@ impl Rasterizer {
     /// Adds a line segment to be rasterized.
     pub fn line_to(&mut self, point: Vec2) {
         if point != self.last {
-            let x_dir = (point.x - self.last.x).signum() as i16;
-            let y_dir = (point.y - self.last.y).signum() as i16;
-            let dtdx = 1.0 / (point.x - self.last.x);
-            let dtdy = 1.0 / (point.y - self.last.y);
-            let mut x = self.last.x.floor() as i16;
-            let mut y = self.last.y.floor() as i16;
-            let mut row_t0: f32 = 0.0;
-            let mut col_t0: f32 = 0.0;
-            let mut row_t1 = if self.last.y == point.y {
-                std::f32::INFINITY
+            if self.fixed_point {
+                self.line_to_fixed(point);
+            } else {
+                self.line_to_float(point);
+            }
+        }
+
+        self.last = point;
+    }
+
+    fn line_to_float(&mut self, point: Vec2) {
+        let x_dir = (point.x - self.last.x).signum() as i16;
+        let y_dir = (point.y - self.last.y).signum() as i16;
+        let dtdx = 1.0 / (point.x - self.last.x);
+        let dtdy = 1.0 / (point.y - self.last.y);
+        let mut x = self.last.x.floor() as i16;
+        let mut y = self.last.y.floor() as i16;
+        let mut row_t0: f32 = 0.0;
+        let mut col_t0: f32 = 0.0;
+        let mut row_t1 = if self.last.y == point.y {
+            f32::INFINITY
+        } else {
+            let next_y = if point.y > self.last.y { (y + 1) as f32 } else { y as f32 };
+            (dtdy * (next_y - self.last.y)).min(1.0)
+        };
+        let mut col_t1 = if self.last.x == point.x {
+            f32::INFINITY
+        } else {
+            let next_x = if point.x > self.last.x { (x + 1) as f32 } else { x as f32 };
+            (dtdx * (next_x - self.last.x)).min(1.0)
+        };
+        let x_step = dtdx.abs();
+        let y_step = dtdy.abs();
+        // Scaling the quantized y coordinates (rather than the heights derived
+        // from them) keeps the heights summing exactly across a row.
+        let y_one = INCREMENT_ONE * self.coverage;
+
+        loop {
+            let t0 = row_t0.max(col_t0);
+            let t1 = row_t1.min(col_t1);
+            let p0 = (1.0 - t0) * self.last + t0 * point;
+            let p1 = (1.0 - t1) * self.last + t1 * point;
+            let x0 = ((p0.x - x as f32) * INCREMENT_ONE + 0.5) as i32;
+            let y0 = ((p0.y - y as f32) * y_one + 0.5) as i32;
+            let x1 = ((p1.x - x as f32) * INCREMENT_ONE + 0.5) as i32;
+            let y1 = ((p1.y - y as f32) * y_one + 0.5) as i32;
+            let height = y1 - y0;
+            let width = (2 << INCREMENT_BITS) - x0 - x1;
+            let area = (height * width + (1 << INCREMENT_BITS)) >> (INCREMENT_BITS + 1);
+
+            self.increment(x, y, area, height);
+
+            if row_t1 < col_t1 {
+                row_t0 = row_t1;
+                row_t1 = (row_t1 + y_step).min(1.0);
+                y += y_dir;
             } else {
-                let next_y = if point.y > self.last.y { (y + 1) as f32 } else { y as f32 };
-                (dtdy * (next_y - self.last.y)).min(1.0)
+                col_t0 = col_t1;
+                col_t1 = (col_t1 + x_step).min(1.0);
+                x += x_dir;
+            }
+
+            if row_t0 == 1.0 || col_t0 == 1.0 {
+                x = point.x.floor() as i16;
+                y = point.y.floor() as i16;
+            }
+
+            let tile_y = y.wrapping_div_euclid(TILE_SIZE as i16);
+            if tile_y != self.tile_y_prev {
+                self.tile_increments.push(TileIncrement {
+                    tile_x: x.wrapping_div_euclid(TILE_SIZE as i16),
+                    tile_y: self.tile_y_prev.min(tile_y),
+                    sign: (tile_y - self.tile_y_prev) as i8,
+                });
+                self.tile_y_prev = tile_y;
+            }
+
+            if row_t0 == 1.0 || col_t0 == 1.0 {
+                break;
+            }
+        }
+    }
+
+    fn line_to_fixed(&mut self, point: Vec2) {
+        #[inline]
+        fn to_fixed(x: f32) -> i32 {
+            (x * FIXED_ONE).round() as i32
+        }
+
+        #[inline]
+        fn div_round(n: i64, d: i64) -> i32 {
+            let (n, d) = if d < 0 { (-n, -d) } else { (n, d) };
+            (2 * n + d).div_euclid(2 * d) as i32
+        }
+
+        let x0 = to_fixed(self.last.x);
+        let y0 = to_fixed(self.last.y);
+        let x1 = to_fixed(point.x);
+        let y1 = to_fixed(point.y);
+        let dx = x1 - x0;
+        let dy = y1 - y0;
+        if dx == 0 && dy == 0 {
+            return;
+        }
+
+        let coverage = (self.coverage * FIXED_ONE).round() as i64;
+
+        let x_at = |y: i32| x0 + div_round(dx as i64 * (y - y0) as i64, dy as i64);
+        let y_at = |x: i32| y0 + div_round(dy as i64 * (x - x0) as i64, dx as i64);
+
+        // Crossings of pixel boundaries are half-open: a point lying exactly
+        // on a boundary belongs to the pixel below or to the right of it.
+        let (row_first, row_last) = if dy > 0 {
+            (y0 >> FIXED_BITS, (y1 - 1) >> FIXED_BITS)
+        } else if dy < 0 {
+            ((y0 - 1) >> FIXED_BITS, y1 >> FIXED_BITS)
+        } else {
+            (y0 >> FIXED_BITS, y0 >> FIXED_BITS)
+        };
+
+        let mut row = row_first;
+        loop {
+            let top = row << FIXED_BITS;
+            let bottom = (row + 1) << FIXED_BITS;
+            let (ya, yb) = if dy > 0 {
+                (y0.max(top), y1.min(bottom))
+            } else if dy < 0 {
+                (y0.min(bottom), y1.max(top))
+            } else {
+                (y0, y1)
             };
-            let mut col_t1 = if self.last.x == point.x {
-                std::f32::INFINITY
+            let xa = if ya == y0 { x0 } else { x_at(ya) };
+            let xb = if yb == y1 { x1 } else { x_at(yb) };
+
+            let (col_first, col_last) = if dx > 0 {
+                (xa >> FIXED_BITS, ((xb - 1) >> FIXED_BITS).max(xa >> FIXED_BITS))
+            } else if dx < 0 {
+                ((xa - 1) >> FIXED_BITS, (xb >> FIXED_BITS).min((xa - 1) >> FIXED_BITS))
             } else {
-                let next_x = if point.x > self.last.x { (x + 1) as f32 } else { x as f32 };
-                (dtdx * (next_x - self.last.x)).min(1.0)
+                (xa >> FIXED_BITS, xa >> FIXED_BITS)
             };
-            let x_step = dtdx.abs();
-            let y_step = dtdy.abs();
 
+            let tile_boundary = (row + 1) % TILE_SIZE as i32 == 0;
+            if dy < 0 && ya == bottom && tile_boundary {
+                self.tile_increments.push(TileIncrement {
+                    tile_x: (col_first >> 3) as i16,
+                    tile_y: (row >> 3) as i16,
+                    sign: -1,
+                });
+            }
+
+            let mut col = col_first;
+            let mut px = xa;
+            let mut py = ya;
             loop {
-                let t0 = row_t0.max(col_t0);
-                let t1 = row_t1.min(col_t1);
-                let p0 = (1.0 - t0) * self.last + t0 * point;
-                let p1 = (1.0 - t1) * self.last + t1 * point;
-                let height = p1.y - p0.y;
-                let right = (x + 1) as f32;
-                let area = 0.5 * height * ((right - p0.x) + (right - p1.x));
-
-                self.increments.push(Increment { x, y, area, height });
-
-                if row_t1 < col_t1 {
-                    row_t0 = row_t1;
-                    row_t1 = (row_t1 + y_step).min(1.0);
-                    y += y_dir;
+                let (qx, qy) = if col == col_last {
+                    (xb, yb)
                 } else {
-                    col_t0 = col_t1;
-                    col_t1 = (col_t1 + x_step).min(1.0);
-                    x += x_dir;
+                    let qx = if dx > 0 { (col + 1) << FIXED_BITS } else { col << FIXED_BITS };
+                    (qx, y_at(qx).max(ya.min(yb)).min(ya.max(yb)))
+                };
+
+                let left = col << FIXED_BITS;
+                let scale = |y: i32| (((y - top) as i64 * coverage) >> FIXED_BITS) as i32;
+                let height = scale(qy) - scale(py);
+                let width = (2 << FIXED_BITS) - (px - left) - (qx - left);
+                let area = (height * width + (1 << (2 * FIXED_BITS - INCREMENT_BITS))) >> (2 * FIXED_BITS + 1 - INCREMENT_BITS);
+                self.increment(col as i16, row as i16, area, height << (INCREMENT_BITS - FIXED_BITS));
+
+                if col == col_last {
+                    break;
                 }
+                col += if dx > 0 { 1 } else { -1 };
+                px = qx;
+                py = qy;
+            }
 
-                if row_t0 == 1.0 || col_t0 == 1.0 {
-                    x = point.x.floor() as i16;
-                    y = point.y.floor() as i16;
-                }
+            if dy > 0 && yb == bottom && tile_boundary {
+                self.tile_increments.push(TileIncrement {
+                    tile_x: (col_last >> 3) as i16,
+                    tile_y: (row >> 3) as i16,
+                    sign: 1,
+                });
+            }
 
-                let tile_y = y.wrapping_div_euclid(TILE_SIZE as i16);
-                if tile_y != self.tile_y_prev {
-                    self.tile_increments.push(TileIncrement {
-                        tile_x: x.wrapping_div_euclid(TILE_SIZE as i16),
-                        tile_y: self.tile_y_prev.min(tile_y),
-                        sign: (tile_y - self.tile_y_prev) as i8,
-                    });
-                    self.tile_y_prev = tile_y;
-                }
+            if row == row_last {
+                break;
+            }
+            row += if dy > 0 { 1 } else { -1 };
+        }
+    }
 
-                if row_t0 == 1.0 || col_t0 == 1.0 {
-                    break;
+    /// Records an increment, merging it into the previous one if both fall in
+    /// the same pixel.
+    #[inline]
+    fn increment(&mut self, x: i16, y: i16, area: i32, height: i32) {
+        if let Some(last) = self.increments.last_mut() {
+            if last.x == x && last.y == y {
+                let merged_area = i16::try_from(last.area as i32 + area);
+                let merged_height = i16::try_from(last.height as i32 + height);
+                if let (Ok(area), Ok(height)) = (merged_area, merged_height) {
+                    last.area = area;
+                    last.height = height;
+                    return;
                 }
             }
         }
 
-        self.last = point;
+        self.increments.push(Increment { x, y, area: area as i16, height: height as i16 });
     }
 
     /// Adds a [`PathCmd`] to be rasterized.
@@ -164,20 +508,176 @@ impl Rasterizer {
         }
     }
 
-    /// Adds a path to be rasterized as a stroke with the given width, applying
+    /// Adds a path to be rasterized as a stroke with the given style, applying
     /// the given transform.
-    pub fn stroke(&mut self, path: &[PathCmd], width: f32, transform: Transform) {
-        self.fill(&stroke(&flatten(path, TOLERANCE), width), transform);
+    ///
+    /// The stroke is computed before the transform is applied, so its width
+    /// and dash lengths are scaled along with the path, and the tolerance used
+    /// for approximating its outline is adjusted for the scale of the
+    /// transform.
+    pub fn stroke(&mut self, path: &[PathCmd], style: &StrokeStyle, transform: Transform) {
+        let scale = transform.matrix.max_scale();
+        if scale > 0.0 {
+            self.fill(&stroke_curves(path, style, TOLERANCE / scale), transform);
+        }
+    }
+
+    /// Adds a path to be rasterized as a stroke with the given style, applying
+    /// the given transform to the path but not to the stroke.
+    ///
+    /// The stroke is computed after the transform is applied, so its width and
+    /// dash lengths are given in device pixels regardless of the transform, as
+    /// with SVG's `vector-effect="non-scaling-stroke"`.
+    pub fn stroke_device_space(&mut self, path: &[PathCmd], style: &StrokeStyle, transform: Transform) {
+        let path: Vec<PathCmd> = path.iter().map(|command| command.transform(transform)).collect();
+        self.fill(&stroke_curves(&path, style, TOLERANCE), Transform::id());
+    }
+
+    /// Adds a path to be rasterized as a hairline with the given width in
+    /// device pixels, applying the given transform to the path but not to the
+    /// width.
+    ///
+    /// Each line segment of the transformed path is drawn as a 1-pixel-wide
+    /// line with its coverage scaled by the width, which keeps lines narrower
+    /// than a pixel smooth and prevents them from dropping out. Widths greater
+    /// than 1 are treated as 1.
+    pub fn hairline(&mut self, path: &[PathCmd], width: f32, transform: Transform) {
+        if self.last != self.first {
+            self.line_to(self.first);
+        }
+
+        self.coverage = width.clamp(0.0, 1.0);
+
+        let mut points = Vec::new();
+        let mut last = Vec2::new(0.0, 0.0);
+        for command in path {
+            command.transform(transform).flatten(last, TOLERANCE, |cmd| match cmd {
+                PathCmd::Move(point) => {
+                    self.hairline_contour(&points, false);
+                    points.clear();
+                    points.push(point);
+                    last = point;
+                }
+                PathCmd::Line(point) => {
+                    if points.is_empty() {
+                        points.push(last);
+                    }
+                    if point != last {
+                        points.push(point);
+                    }
+                    last = point;
+                }
+                PathCmd::Close => {
+                    if points.len() > 1 && points[points.len() - 1] == points[0] {
+                        points.pop();
+                    }
+                    self.hairline_contour(&points, true);
+                    if let Some(&first) = points.first() {
+                        last = first;
+                    }
+                    points.clear();
+                }
+                _ => {}
+            });
+        }
+        self.hairline_contour(&points, false);
+
+        self.coverage = 1.0;
+    }
+
+    /// Draws each segment of a flattened contour as a 1-pixel-wide
+    /// quadrilateral. Adjacent segments share an edge along the bisector of
+    /// the angle between them, so that their coverage isn't counted twice.
+    fn hairline_contour(&mut self, points: &[Vec2], closed: bool) {
+        if points.len() < 2 {
+            return;
+        }
+
+        let count = if closed { points.len() } else { points.len() - 1 };
+        let normal = |i: usize| {
+            let tangent = points[(i + 1) % points.len()] - points[i];
+            (0.5 / tangent.length()) * Vec2::new(-tangent.y, tangent.x)
+        };
+        // The offset to the left edge at the vertex between two segments,
+        // lying on the bisector of the angle between them. For turns sharper
+        // than 120 degrees it would extend more than a pixel, so the segments
+        // are given square ends instead.
+        let join = |prev: Vec2, next: Vec2| {
+            let sum = prev + next;
+            let dot = sum.dot(sum);
+            if dot >= 0.25 {
+                Some((0.5 / dot) * sum)
+            } else {
+                None
+            }
+        };
+
+        for i in 0..count {
+            let normal_i = normal(i);
+            let start = if closed || i > 0 { join(normal((i + count - 1) % count), normal_i) } else { None };
+            let end = if closed || i + 1 < count { join(normal_i, normal((i + 1) % count)) } else { None };
+            let start = start.unwrap_or(normal_i);
+            let end = end.unwrap_or(normal_i);
+
+            let from = points[i];
+            let to = points[(i + 1) % points.len()];
+            self.move_to(from + start);
+            self.line_to(to + end);
+            self.line_to(to - end);
+            self.line_to(from - start);
+            self.line_to(from + start);
+        }
+    }
+
+    /// Restricts the output of the rasterizer to the interior of the given
+    /// path, applying the given transform.
+    ///
+    /// Clipping is cumulative: if a clip has already been set, the output is
+    /// restricted to the intersection of both clips.
+    pub fn clip(&mut self, path: &[PathCmd], transform: Transform) {
+        let mut rasterizer = Rasterizer {
+            fixed_point: self.fixed_point,
+            ..Rasterizer::new()
+        };
+        rasterizer.fill(path, transform);
+
+        let mut mask = TileList::new();
+        rasterizer.finish(&mut mask);
+        self.clip_mask(mask);
+    }
+
+    /// Restricts the output of the rasterizer to the coverage of the given
+    /// [`TileList`].
+    ///
+    /// Clipping is cumulative, as with [`Rasterizer::clip`].
+    ///
+    /// [`TileList`]: crate::TileList
+    pub fn clip_mask(&mut self, mask: TileList) {
+        self.clip = Some(match self.clip.take() {
+            Some(clip) => clip.intersect(&mask),
+            None => mask,
+        });
     }
 
     /// Rasterizes the accumulated path data, passing the results to the given
     /// [`TileBuilder`]. Consumes the rasterizer.
     ///
     /// The path is rasterized to a set of 8×8 alpha mask tiles and n×8 solid
-    /// interior spans.
+    /// interior spans. If a clip has been set, the output is intersected with
+    /// it tile by tile: tiles and spans outside the clip are never emitted, and
+    /// spans partially covered by the clip are emitted as alpha mask tiles.
     ///
     /// [`TileBuilder`]: crate::TileBuilder
     pub fn finish<B: TileBuilder>(mut self, builder: &mut B) {
+        if let Some(clip) = self.clip.take() {
+            let mask = clip.mask();
+            self.rasterize(&mut mask.clip(builder));
+        } else {
+            self.rasterize(builder);
+        }
+    }
+
+    fn rasterize<B: TileBuilder>(mut self, builder: &mut B) {
         if self.last != self.first {
             self.line_to(self.first);
         }
@@ -193,8 +693,8 @@ impl Rasterizer {
         let mut bins = Vec::new();
         let mut bin = Bin { tile_x: 0, tile_y: 0, start: 0, end: 0 };
         if let Some(first) = self.increments.first() {
-            bin.tile_x = (first.x as i16).wrapping_div_euclid(TILE_SIZE as i16);
-            bin.tile_y = (first.y as i16).wrapping_div_euclid(TILE_SIZE as i16);
+            bin.tile_x = first.x.wrapping_div_euclid(TILE_SIZE as i16);
+            bin.tile_y = first.y.wrapping_div_euclid(TILE_SIZE as i16);
         }
         for (i, increment) in self.increments.iter().enumerate() {
             let tile_x = increment.x.wrapping_div_euclid(TILE_SIZE as i16);
@@ -206,14 +706,14 @@ impl Rasterizer {
             bin.end += 1;
         }
         bins.push(bin);
-        bins.sort_unstable_by_key(|bin| (bin.tile_y, bin.tile_x));
+        sort_by_tile(&mut bins, |bin| (bin.tile_x, bin.tile_y));
 
-        self.tile_increments.sort_unstable_by_key(|tile_inc| (tile_inc.tile_y, tile_inc.tile_x));
+        sort_by_tile(&mut self.tile_increments, |tile_inc| (tile_inc.tile_x, tile_inc.tile_y));
 
-        let mut areas = [0.0; TILE_SIZE * TILE_SIZE];
-        let mut heights = [0.0; TILE_SIZE * TILE_SIZE];
-        let mut prev = [0.0; TILE_SIZE];
-        let mut next = [0.0; TILE_SIZE];
+        let mut areas = [0i32; TILE_SIZE * TILE_SIZE];
+        let mut heights = [0i32; TILE_SIZE * TILE_SIZE];
+        let mut prev = [0i32; TILE_SIZE];
+        let mut next = [0i32; TILE_SIZE];
 
         let mut tile_increments_i = 0;
         let mut winding = 0;
@@ -223,31 +723,23 @@ impl Rasterizer {
             for increment in &self.increments[bin.start..bin.end] {
                 let x = (increment.x as usize).wrapping_rem_euclid(TILE_SIZE);
                 let y = (increment.y as usize).wrapping_rem_euclid(TILE_SIZE);
-                areas[(y * TILE_SIZE + x) as usize] += increment.area;
-                heights[(y * TILE_SIZE + x) as usize] += increment.height;
+                areas[y * TILE_SIZE + x] += increment.area as i32;
+                heights[y * TILE_SIZE + x] += increment.height as i32;
             }
 
             if i + 1 == bins.len() || bins[i + 1].tile_x != bin.tile_x || bins[i + 1].tile_y != bin.tile_y {
-                let mut tile = [0; TILE_SIZE * TILE_SIZE];
-                for y in 0..TILE_SIZE {
-                    let mut accum = prev[y];
-                    for x in 0..TILE_SIZE {
-                        tile[y * TILE_SIZE + x] = ((accum + areas[y * TILE_SIZE + x]).abs() * 256.0).min(255.0) as u8;
-                        accum += heights[y * TILE_SIZE + x];
-                    }
-                    next[y] = accum;
-                }
+                let tile = accumulate_tile(&prev, &areas, &heights, &mut next);
 
                 builder.tile(bin.tile_x * TILE_SIZE as i16, bin.tile_y * TILE_SIZE as i16, tile);
 
-                areas = [0.0; TILE_SIZE * TILE_SIZE];
-                heights = [0.0; TILE_SIZE * TILE_SIZE];
+                areas = [0; TILE_SIZE * TILE_SIZE];
+                heights = [0; TILE_SIZE * TILE_SIZE];
                 if i + 1 < bins.len() && bins[i + 1].tile_y == bin.tile_y {
                     prev = next;
                 } else {
-                    prev = [0.0; TILE_SIZE];
+                    prev = [0; TILE_SIZE];
                 }
-                next = [0.0; TILE_SIZE];
+                next = [0; TILE_SIZE];
 
                 if i + 1 < bins.len() && bins[i + 1].tile_y == bin.tile_y && bins[i + 1].tile_x > bin.tile_x + 1 {
                     while tile_increments_i < self.tile_increments.len() {
@@ -267,3 +759,145 @@ impl Rasterizer {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{LineCap, LineJoin, Mat2x2};
+
+    /// Hashes the tiles and spans produced by a rasterizer using FNV-1a.
+    struct Hasher(u64);
+
+    impl Hasher {
+        fn write(&mut self, bytes: &[u8]) {
+            for &byte in bytes {
+                self.0 = (self.0 ^ byte as u64).wrapping_mul(0x100_0000_01b3);
+            }
+        }
+    }
+
+    impl TileBuilder for Hasher {
+        fn tile(&mut self, x: i16, y: i16, data: [u8; TILE_SIZE * TILE_SIZE]) {
+            self.write(&[0]);
+            self.write(&x.to_le_bytes());
+            self.write(&y.to_le_bytes());
+            self.write(&data);
+        }
+
+        fn span(&mut self, x: i16, y: i16, width: u16) {
+            self.write(&[1]);
+            self.write(&x.to_le_bytes());
+            self.write(&y.to_le_bytes());
+            self.write(&width.to_le_bytes());
+        }
+    }
+
+    fn hash(rasterizer: Rasterizer) -> u64 {
+        let mut hasher = Hasher(0xcbf2_9ce4_8422_2325);
+        rasterizer.finish(&mut hasher);
+        hasher.0
+    }
+
+    /// A path exercising every kind of segment, drawn with an arbitrary
+    /// transform.
+    fn reference() -> (Vec<PathCmd>, Transform) {
+        let path = vec![
+            PathCmd::Move(Vec2::new(10.3, 20.7)),
+            PathCmd::Line(Vec2::new(90.1, 15.2)),
+            PathCmd::Quadratic(Vec2::new(130.6, 60.4), Vec2::new(95.9, 110.8)),
+            PathCmd::Cubic(Vec2::new(60.2, 150.3), Vec2::new(20.4, 70.1), Vec2::new(45.7, 60.6)),
+            PathCmd::Conic(Vec2::new(5.5, 50.5), Vec2::new(10.3, 20.7), 0.6),
+            PathCmd::Close,
+            PathCmd::Move(Vec2::new(50.0, 40.0)),
+            PathCmd::Line(Vec2::new(70.0, 45.0)),
+            PathCmd::Line(Vec2::new(55.0, 80.0)),
+            PathCmd::Close,
+        ];
+        let transform = Transform::new(Mat2x2::new(1.3, -0.4, 0.35, 0.9), Vec2::new(17.25, -3.6));
+        (path, transform)
+    }
+
+    #[test]
+    fn fixed_point_output_is_reproducible() {
+        let (path, transform) = reference();
+
+        let mut rasterizer = Rasterizer::fixed_point();
+        rasterizer.fill(&path, transform);
+        assert_eq!(hash(rasterizer), 0xfc63caec17af2baf);
+
+        let style = StrokeStyle {
+            cap: LineCap::Round,
+            join: LineJoin::Round,
+            ..StrokeStyle::new(7.5)
+        };
+        let mut rasterizer = Rasterizer::fixed_point();
+        rasterizer.stroke(&path, &style, transform);
+        assert_eq!(hash(rasterizer), 0x5f68c4354e017fbf);
+
+        let mut rasterizer = Rasterizer::fixed_point();
+        rasterizer.hairline(&path, 0.6, transform);
+        assert_eq!(hash(rasterizer), 0x067c42b14049695a);
+    }
+
+    #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+    #[test]
+    fn accumulate_tile_sse2_matches_scalar() {
+        if !is_x86_feature_detected!("sse2") {
+            return;
+        }
+
+        // A linear congruential generator producing values in `-limit..limit`.
+        let mut state = 0x2545_f491_4f6c_dd1du64;
+        let mut random = |limit: i32| {
+            state = state.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            ((state >> 33) as i64 % (2 * limit as i64)) as i32 - limit
+        };
+
+        // The limits range from values typical of real paths up to ones large
+        // enough to saturate when packed, while keeping the sums of a row
+        // within `i32`.
+        for &limit in &[1, 1 << INCREMENT_BITS, 1 << (INCREMENT_BITS + 4), 1 << 26] {
+            for _ in 0..1000 {
+                let mut prev = [0; TILE_SIZE];
+                let mut areas = [0; TILE_SIZE * TILE_SIZE];
+                let mut heights = [0; TILE_SIZE * TILE_SIZE];
+                prev.iter_mut().for_each(|value| *value = 4 * random(limit));
+                areas.iter_mut().for_each(|value| *value = 4 * random(limit));
+                heights.iter_mut().for_each(|value| *value = random(limit));
+
+                let mut scalar_next = [0; TILE_SIZE];
+                let mut sse2_next = [0; TILE_SIZE];
+                let scalar = accumulate_tile_scalar(&prev, &areas, &heights, &mut scalar_next);
+                let sse2 = unsafe { accumulate_tile_sse2(&prev, &areas, &heights, &mut sse2_next) };
+                assert_eq!(scalar[..], sse2[..]);
+                assert_eq!(scalar_next, sse2_next);
+            }
+        }
+
+        // Edge cases: all zero, full coverage of either sign, and values
+        // just below and above the point where coverage saturates.
+        let one = 1 << INCREMENT_BITS;
+        for &(prev, area, height) in &[
+            (0, 0, 0),
+            (one, 0, 0),
+            (-one, 0, 0),
+            (0, one - 1, 0),
+            (0, one, 0),
+            (0, -one - 1, 0),
+            (one, 0, one),
+            (-one, 0, -one),
+            (1 << 28, 1 << 28, 1 << 24),
+            (-(1 << 28), -(1 << 28), -(1 << 24)),
+        ] {
+            let prev = [prev; TILE_SIZE];
+            let areas = [area; TILE_SIZE * TILE_SIZE];
+            let heights = [height; TILE_SIZE * TILE_SIZE];
+            let mut scalar_next = [0; TILE_SIZE];
+            let mut sse2_next = [0; TILE_SIZE];
+            let scalar = accumulate_tile_scalar(&prev, &areas, &heights, &mut scalar_next);
+            let sse2 = unsafe { accumulate_tile_sse2(&prev, &areas, &heights, &mut sse2_next) };
+            assert_eq!(scalar[..], sse2[..]);
+            assert_eq!(scalar_next, sse2_next);
+        }
+    }
+}
@@ -0,0 +1,466 @@
+use std::slice;
+
+use crate::{PathCmd, Transform, Vec2};
+
+/// A single segment of a path, consisting of a drawing command together with
+/// the point it starts from.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Segment {
+    Line { p0: Vec2, p1: Vec2 },
+    Quadratic { p0: Vec2, p1: Vec2, p2: Vec2 },
+    Cubic { p0: Vec2, p1: Vec2, p2: Vec2, p3: Vec2 },
+    Conic { p0: Vec2, p1: Vec2, p2: Vec2, weight: f32 },
+}
+
+impl Segment {
+    /// Constructs the segment drawn by the given path command when starting
+    /// from the point `start`, or `None` for commands which don't draw a
+    /// segment by themselves (`Move` and `Close`).
+    pub fn new(start: Vec2, command: PathCmd) -> Option<Segment> {
+        match command {
+            PathCmd::Line(point) => Some(Segment::Line { p0: start, p1: point }),
+            PathCmd::Quadratic(control, point) => {
+                Some(Segment::Quadratic { p0: start, p1: control, p2: point })
+            }
+            PathCmd::Cubic(control1, control2, point) => {
+                Some(Segment::Cubic { p0: start, p1: control1, p2: control2, p3: point })
+            }
+            PathCmd::Conic(control, point, weight) => {
+                Some(Segment::Conic { p0: start, p1: control, p2: point, weight })
+            }
+            PathCmd::Move(_) | PathCmd::Close => None,
+        }
+    }
+
+    /// Returns the path command which draws the segment from its start point.
+    pub fn command(self) -> PathCmd {
+        match self {
+            Segment::Line { p1, .. } => PathCmd::Line(p1),
+            Segment::Quadratic { p1, p2, .. } => PathCmd::Quadratic(p1, p2),
+            Segment::Cubic { p1, p2, p3, .. } => PathCmd::Cubic(p1, p2, p3),
+            Segment::Conic { p1, p2, weight, .. } => PathCmd::Conic(p1, p2, weight),
+        }
+    }
+
+    /// Returns the start point of the segment.
+    pub fn start(self) -> Vec2 {
+        match self {
+            Segment::Line { p0, .. }
+            | Segment::Quadratic { p0, .. }
+            | Segment::Cubic { p0, .. }
+            | Segment::Conic { p0, .. } => p0,
+        }
+    }
+
+    /// Returns the end point of the segment.
+    pub fn end(self) -> Vec2 {
+        match self {
+            Segment::Line { p1, .. } => p1,
+            Segment::Quadratic { p2, .. } | Segment::Conic { p2, .. } => p2,
+            Segment::Cubic { p3, .. } => p3,
+        }
+    }
+
+    /// Applies the given transform to the segment.
+    pub fn transform(self, transform: Transform) -> Segment {
+        let start = transform.apply(self.start());
+        Segment::new(start, self.command().transform(transform)).unwrap()
+    }
+
+    /// Evaluates the position of the segment at the parameter `t`.
+    pub fn eval(self, t: f32) -> Vec2 {
+        match self {
+            Segment::Line { p0, p1 } => Vec2::lerp(t, p0, p1),
+            Segment::Quadratic { p0, p1, p2 } => {
+                let p01 = Vec2::lerp(t, p0, p1);
+                let p12 = Vec2::lerp(t, p1, p2);
+                Vec2::lerp(t, p01, p12)
+            }
+            Segment::Cubic { p0, p1, p2, p3 } => {
+                let p01 = Vec2::lerp(t, p0, p1);
+                let p12 = Vec2::lerp(t, p1, p2);
+                let p23 = Vec2::lerp(t, p2, p3);
+                let p012 = Vec2::lerp(t, p01, p12);
+                let p123 = Vec2::lerp(t, p12, p23);
+                Vec2::lerp(t, p012, p123)
+            }
+            Segment::Conic { p0, p1, p2, weight } => {
+                let p01 = Vec2::lerp(t, p0, weight * p1);
+                let p12 = Vec2::lerp(t, weight * p1, p2);
+                let denom = (1.0 - t) * (1.0 - t) + 2.0 * t * (1.0 - t) * weight + t * t;
+                (1.0 / denom) * Vec2::lerp(t, p01, p12)
+            }
+        }
+    }
+
+    /// Evaluates the derivative of the segment with respect to its parameter
+    /// at `t`.
+    pub fn derivative(self, t: f32) -> Vec2 {
+        self.derivatives(t).1
+    }
+
+    /// Evaluates the position and the first and second derivatives of the
+    /// segment at the parameter `t`.
+    pub(crate) fn derivatives(self, t: f32) -> (Vec2, Vec2, Vec2) {
+        let s = 1.0 - t;
+        match self {
+            Segment::Line { p0, p1 } => (Vec2::lerp(t, p0, p1), p1 - p0, Vec2::new(0.0, 0.0)),
+            Segment::Quadratic { p0, p1, p2 } => (
+                (s * s) * p0 + (2.0 * s * t) * p1 + (t * t) * p2,
+                2.0 * (s * (p1 - p0) + t * (p2 - p1)),
+                2.0 * (p2 - 2.0 * p1 + p0),
+            ),
+            Segment::Cubic { p0, p1, p2, p3 } => (
+                (s * s * s) * p0 + (3.0 * s * s * t) * p1 + (3.0 * s * t * t) * p2 + (t * t * t) * p3,
+                3.0 * ((s * s) * (p1 - p0) + (2.0 * s * t) * (p2 - p1) + (t * t) * (p3 - p2)),
+                6.0 * (s * (p2 - 2.0 * p1 + p0) + t * (p3 - 2.0 * p2 + p1)),
+            ),
+            Segment::Conic { p0, p1, p2, weight } => {
+                // Differentiate the quotient of the numerator and denominator
+                // of the rational parametrization.
+                let numerator = (s * s) * p0 + (2.0 * s * t * weight) * p1 + (t * t) * p2;
+                let numerator_1 = 2.0 * (s * (weight * p1 - p0) + t * (p2 - weight * p1));
+                let numerator_2 = 2.0 * (p2 - (2.0 * weight) * p1 + p0);
+                let denominator = s * s + 2.0 * s * t * weight + t * t;
+                let denominator_1 = 2.0 * (s * (weight - 1.0) + t * (1.0 - weight));
+                let denominator_2 = 2.0 * (2.0 - 2.0 * weight);
+                let position = (1.0 / denominator) * numerator;
+                let first = (1.0 / denominator) * (numerator_1 - denominator_1 * position);
+                let second = (1.0 / denominator)
+                    * (numerator_2 - (2.0 * denominator_1) * first - denominator_2 * position);
+                (position, first, second)
+            }
+        }
+    }
+
+    /// Returns the portion of the segment between the parameters `t0` and
+    /// `t1`, as a segment of the same type.
+    ///
+    /// Conics are renormalized to have unit end weights, so parameters along
+    /// the result don't map linearly onto those of the original.
+    pub fn subsegment(self, t0: f32, t1: f32) -> Segment {
+        match self {
+            Segment::Line { p0, p1 } => {
+                Segment::Line { p0: Vec2::lerp(t0, p0, p1), p1: Vec2::lerp(t1, p0, p1) }
+            }
+            Segment::Quadratic { p0, p1, p2 } => {
+                let blossom = |u: f32, v: f32| {
+                    Vec2::lerp(v, Vec2::lerp(u, p0, p1), Vec2::lerp(u, p1, p2))
+                };
+                Segment::Quadratic { p0: blossom(t0, t0), p1: blossom(t0, t1), p2: blossom(t1, t1) }
+            }
+            Segment::Cubic { p0, p1, p2, p3 } => {
+                let blossom = |u: f32, v: f32, w: f32| {
+                    let p01 = Vec2::lerp(u, p0, p1);
+                    let p12 = Vec2::lerp(u, p1, p2);
+                    let p23 = Vec2::lerp(u, p2, p3);
+                    Vec2::lerp(w, Vec2::lerp(v, p01, p12), Vec2::lerp(v, p12, p23))
+                };
+                Segment::Cubic {
+                    p0: blossom(t0, t0, t0),
+                    p1: blossom(t0, t0, t1),
+                    p2: blossom(t0, t1, t1),
+                    p3: blossom(t1, t1, t1),
+                }
+            }
+            Segment::Conic { p0, p1, p2, weight } => {
+                // Split the conic as a quadratic in homogeneous coordinates,
+                // then renormalize it so that its endpoints have unit weight.
+                let blossom = |u: f32, v: f32| {
+                    let lerp = |t: f32, a: (Vec2, f32), b: (Vec2, f32)| {
+                        (Vec2::lerp(t, a.0, b.0), (1.0 - t) * a.1 + t * b.1)
+                    };
+                    let p0 = (p0, 1.0);
+                    let p1 = (weight * p1, weight);
+                    let p2 = (p2, 1.0);
+                    lerp(v, lerp(u, p0, p1), lerp(u, p1, p2))
+                };
+                let (q0, w0) = blossom(t0, t0);
+                let (q1, w1) = blossom(t0, t1);
+                let (q2, w2) = blossom(t1, t1);
+                Segment::Conic {
+                    p0: (1.0 / w0) * q0,
+                    p1: (1.0 / w1) * q1,
+                    p2: (1.0 / w2) * q2,
+                    weight: w1 / (w0 * w2).sqrt(),
+                }
+            }
+        }
+    }
+
+    /// Splits the segment into two at the parameter `t`.
+    ///
+    /// ```
+    /// use ochre::{Segment, Vec2};
+    ///
+    /// let segment = Segment::Quadratic {
+    ///     p0: Vec2::new(0.0, 0.0),
+    ///     p1: Vec2::new(1.0, 2.0),
+    ///     p2: Vec2::new(2.0, 0.0),
+    /// };
+    /// let (first, second) = segment.split(0.5);
+    /// assert_eq!(first.end(), Vec2::new(1.0, 1.0));
+    /// assert_eq!(second.start(), first.end());
+    /// ```
+    pub fn split(self, t: f32) -> (Segment, Segment) {
+        (self.subsegment(0.0, t), self.subsegment(t, 1.0))
+    }
+
+    /// Finds the parameters strictly between 0 and 1 at which the segment
+    /// reaches a local extremum in either x or y, in increasing order.
+    ///
+    /// Parameters within a small distance of 0 or 1 are left out, since they
+    /// usually stem from rounding error in extrema lying exactly at the
+    /// endpoints (as with the quarter circles of [`Path::circle`]).
+    ///
+    /// ```
+    /// use ochre::{Segment, Vec2};
+    ///
+    /// let quarter_circle = Segment::Conic {
+    ///     p0: Vec2::new(100.0, 0.0),
+    ///     p1: Vec2::new(100.0, 100.0),
+    ///     p2: Vec2::new(0.0, 100.0),
+    ///     weight: std::f32::consts::FRAC_1_SQRT_2,
+    /// };
+    /// assert!(quarter_circle.extrema().is_empty());
+    /// ```
+    ///
+    /// [`Path::circle`]: crate::Path::circle
+    pub fn extrema(self) -> Vec<f32> {
+        let mut roots = Vec::new();
+        match self {
+            Segment::Line { .. } => {}
+            Segment::Quadratic { p0, p1, p2 } => {
+                let a = p0 - 2.0 * p1 + p2;
+                let b = p1 - p0;
+                for (a, b) in [(a.x, b.x), (a.y, b.y)] {
+                    if a != 0.0 {
+                        let t = -b / a;
+                        if is_interior(t) {
+                            roots.push(t);
+                        }
+                    }
+                }
+            }
+            Segment::Cubic { p0, p1, p2, p3 } => {
+                let a = p3 - 3.0 * p2 + 3.0 * p1 - p0;
+                let b = 2.0 * (p2 - 2.0 * p1 + p0);
+                let c = p1 - p0;
+                for (a, b, c) in [(a.x, b.x, c.x), (a.y, b.y, c.y)] {
+                    quadratic_roots(a, b, c, |t| roots.push(t));
+                }
+            }
+            Segment::Conic { p0, p1, p2, weight } => {
+                // The derivative of the quotient of the numerator and
+                // denominator of each coordinate is zero where a quadratic
+                // expression in their coefficients is. Working relative to
+                // `p0` avoids cancellation far from the origin.
+                let (p1, p2) = (p1 - p0, p2 - p0);
+                let a_d = 2.0 - 2.0 * weight;
+                let a_n = p2 - (2.0 * weight) * p1;
+                let b_n = (2.0 * weight) * p1;
+                for (p2, a_n, b_n) in [(p2.x, a_n.x, b_n.x), (p2.y, a_n.y, b_n.y)] {
+                    quadratic_roots(-a_d * p2, 2.0 * a_n, b_n, |t| roots.push(t));
+                }
+            }
+        }
+        sort(&mut roots);
+        roots
+    }
+
+    /// Finds the parameters strictly between 0 and 1 at which the curvature
+    /// of the segment changes sign, in increasing order. As with
+    /// [`extrema`](Segment::extrema), parameters very close to 0 or 1 are
+    /// left out.
+    ///
+    /// Only cubic curves can have inflection points.
+    pub fn inflections(self) -> Vec<f32> {
+        let mut roots = Vec::new();
+        if let Segment::Cubic { p0, p1, p2, p3 } = self {
+            // The cross product of the first and second derivatives is a
+            // quadratic in the parameter.
+            let a = p1 - p0;
+            let b = p2 - 2.0 * p1 + p0;
+            let c = p3 - 3.0 * p2 + 3.0 * p1 - p0;
+            quadratic_roots(b.cross(c), a.cross(c), a.cross(b), |t| roots.push(t));
+        }
+        sort(&mut roots);
+        roots
+    }
+
+    /// Subdivides the segment at its extrema, calling `callback` with a
+    /// sequence of pieces which are each monotonic in both x and y.
+    pub fn monotonic(self, mut callback: impl FnMut(Segment)) {
+        let mut t0 = 0.0;
+        for t1 in self.extrema() {
+            (callback)(self.subsegment(t0, t1));
+            t0 = t1;
+        }
+        (callback)(self.subsegment(t0, 1.0));
+    }
+}
+
+/// An event produced when iterating over the segments of a path with
+/// [`segments`].
+///
+/// [`segments`]: crate::segments
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum PathEvent {
+    /// A contour begins at the given point.
+    Begin(Vec2),
+    /// A segment of the current contour.
+    Segment(Segment),
+    /// The current contour ends, and was either closed explicitly or left
+    /// open.
+    End { closed: bool },
+}
+
+/// An iterator over the segments of a path, created by [`segments`].
+///
+/// [`segments`]: crate::segments
+#[derive(Clone, Debug)]
+pub struct Segments<'a> {
+    commands: slice::Iter<'a, PathCmd>,
+    start: Vec2,
+    last: Vec2,
+    open: bool,
+    pending: Option<PathEvent>,
+}
+
+/// Iterates over the segments of the given path, each along with its start
+/// point.
+///
+/// Each contour is reported as a [`PathEvent::Begin`], followed by its
+/// segments and a [`PathEvent::End`]. A `Close` command produces a line back
+/// to the start of the contour if it doesn't already end there. Drawing
+/// commands which don't follow a `Move` begin a new contour at the current
+/// point.
+///
+/// ```
+/// use ochre::{segments, PathCmd, PathEvent, Segment, Vec2};
+///
+/// let path = [
+///     PathCmd::Move(Vec2::new(0.0, 0.0)),
+///     PathCmd::Line(Vec2::new(1.0, 0.0)),
+///     PathCmd::Close,
+/// ];
+/// let events: Vec<PathEvent> = segments(&path).collect();
+/// assert_eq!(events, [
+///     PathEvent::Begin(Vec2::new(0.0, 0.0)),
+///     PathEvent::Segment(Segment::Line { p0: Vec2::new(0.0, 0.0), p1: Vec2::new(1.0, 0.0) }),
+///     PathEvent::Segment(Segment::Line { p0: Vec2::new(1.0, 0.0), p1: Vec2::new(0.0, 0.0) }),
+///     PathEvent::End { closed: true },
+/// ]);
+/// ```
+///
+/// [`PathEvent::Begin`]: crate::PathEvent::Begin
+/// [`PathEvent::End`]: crate::PathEvent::End
+pub fn segments(path: &[PathCmd]) -> Segments<'_> {
+    Segments {
+        commands: path.iter(),
+        start: Vec2::new(0.0, 0.0),
+        last: Vec2::new(0.0, 0.0),
+        open: false,
+        pending: None,
+    }
+}
+
+impl<'a> Iterator for Segments<'a> {
+    type Item = PathEvent;
+
+    fn next(&mut self) -> Option<PathEvent> {
+        if let Some(event) = self.pending.take() {
+            return Some(event);
+        }
+
+        loop {
+            let command = match self.commands.next() {
+                Some(command) => *command,
+                None => {
+                    if self.open {
+                        self.open = false;
+                        return Some(PathEvent::End { closed: false });
+                    }
+                    return None;
+                }
+            };
+
+            match command {
+                PathCmd::Move(point) => {
+                    self.start = point;
+                    self.last = point;
+                    if self.open {
+                        self.pending = Some(PathEvent::Begin(point));
+                        return Some(PathEvent::End { closed: false });
+                    }
+                    self.open = true;
+                    return Some(PathEvent::Begin(point));
+                }
+                PathCmd::Close => {
+                    if !self.open {
+                        continue;
+                    }
+                    self.open = false;
+                    let last = self.last;
+                    self.last = self.start;
+                    if last != self.start {
+                        self.pending = Some(PathEvent::End { closed: true });
+                        return Some(PathEvent::Segment(Segment::Line { p0: last, p1: self.start }));
+                    }
+                    return Some(PathEvent::End { closed: true });
+                }
+                _ => {
+                    let segment = Segment::new(self.last, command).unwrap();
+                    self.last = segment.end();
+                    if !self.open {
+                        self.open = true;
+                        self.start = segment.start();
+                        self.pending = Some(PathEvent::Segment(segment));
+                        return Some(PathEvent::Begin(segment.start()));
+                    }
+                    return Some(PathEvent::Segment(segment));
+                }
+            }
+        }
+    }
+}
+
+/// Calls `callback` with the roots of `a t^2 + b t + c` lying strictly between
+/// 0 and 1.
+fn quadratic_roots(a: f32, b: f32, c: f32, mut callback: impl FnMut(f32)) {
+    let mut root = |t: f32| {
+        if is_interior(t) {
+            (callback)(t);
+        }
+    };
+
+    if a.abs() <= 1e-6 * (b.abs() + c.abs()) {
+        if b != 0.0 {
+            root(-c / b);
+        }
+        return;
+    }
+
+    let discriminant = b * b - 4.0 * a * c;
+    if discriminant < 0.0 {
+        return;
+    }
+    // Avoid cancellation by computing one root from the other.
+    let q = -0.5 * (b + b.signum() * discriminant.sqrt());
+    root(q / a);
+    if q != 0.0 {
+        root(c / q);
+    }
+}
+
+/// Checks whether a root lies between 0 and 1, excluding those close enough to
+/// either end to be attributed to rounding error.
+fn is_interior(t: f32) -> bool {
+    const EPSILON: f32 = 1e-5;
+    t > EPSILON && t < 1.0 - EPSILON
+}
+
+fn sort(roots: &mut Vec<f32>) {
+    roots.sort_by(|a, b| a.partial_cmp(b).unwrap());
+    roots.dedup();
+}
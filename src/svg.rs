@@ -0,0 +1,488 @@
+use std::fmt;
+
+use crate::{Path, PathCmd, Vec2};
+
+/// The kind of error encountered while parsing SVG path data.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum PathDataErrorKind {
+    /// A command letter was expected.
+    ExpectedCommand,
+    /// The path data did not begin with a moveto command.
+    ExpectedMoveTo,
+    /// A number was expected.
+    ExpectedNumber,
+    /// An arc flag (`0` or `1`) was expected.
+    ExpectedFlag,
+}
+
+/// An error encountered while parsing SVG path data.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct PathDataError {
+    /// The byte offset in the input at which the error occurred.
+    pub offset: usize,
+    pub kind: PathDataErrorKind,
+}
+
+impl fmt::Display for PathDataError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let expected = match self.kind {
+            PathDataErrorKind::ExpectedCommand => "command",
+            PathDataErrorKind::ExpectedMoveTo => "moveto command",
+            PathDataErrorKind::ExpectedNumber => "number",
+            PathDataErrorKind::ExpectedFlag => "flag",
+        };
+        write!(f, "expected {} at byte offset {}", expected, self.offset)
+    }
+}
+
+impl std::error::Error for PathDataError {}
+
+struct Parser<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn peek(&self) -> Option<u8> {
+        self.data.get(self.pos).copied()
+    }
+
+    fn error(&self, kind: PathDataErrorKind) -> PathDataError {
+        PathDataError { offset: self.pos, kind }
+    }
+
+    fn skip_whitespace(&mut self) {
+        while let Some(b' ') | Some(b'\t') | Some(b'\n') | Some(b'\r') | Some(b'\x0C') = self.peek() {
+            self.pos += 1;
+        }
+    }
+
+    fn skip_separator(&mut self) {
+        self.skip_whitespace();
+        if self.peek() == Some(b',') {
+            self.pos += 1;
+            self.skip_whitespace();
+        }
+    }
+
+    fn skip_digits(&mut self) -> usize {
+        let start = self.pos;
+        while let Some(b'0'..=b'9') = self.peek() {
+            self.pos += 1;
+        }
+        self.pos - start
+    }
+
+    /// Returns `true` if the next token could begin a number.
+    fn at_number(&self) -> bool {
+        matches!(self.peek(), Some(b'0'..=b'9') | Some(b'.') | Some(b'-') | Some(b'+'))
+    }
+
+    fn number(&mut self) -> Result<f32, PathDataError> {
+        self.skip_whitespace();
+        let start = self.pos;
+
+        if let Some(b'-') | Some(b'+') = self.peek() {
+            self.pos += 1;
+        }
+        let mut digits = self.skip_digits();
+        if self.peek() == Some(b'.') {
+            self.pos += 1;
+            digits += self.skip_digits();
+        }
+        if digits == 0 {
+            self.pos = start;
+            return Err(self.error(PathDataErrorKind::ExpectedNumber));
+        }
+
+        // Only treat an `e` as the start of an exponent if digits follow.
+        if let Some(b'e') | Some(b'E') = self.peek() {
+            let mantissa_end = self.pos;
+            self.pos += 1;
+            if let Some(b'-') | Some(b'+') = self.peek() {
+                self.pos += 1;
+            }
+            if self.skip_digits() == 0 {
+                self.pos = mantissa_end;
+            }
+        }
+
+        let text = std::str::from_utf8(&self.data[start..self.pos]).unwrap();
+        let value = text.parse().map_err(|_| PathDataError {
+            offset: start,
+            kind: PathDataErrorKind::ExpectedNumber,
+        })?;
+        self.skip_separator();
+        Ok(value)
+    }
+
+    fn point(&mut self) -> Result<Vec2, PathDataError> {
+        let x = self.number()?;
+        let y = self.number()?;
+        Ok(Vec2::new(x, y))
+    }
+
+    fn flag(&mut self) -> Result<bool, PathDataError> {
+        self.skip_whitespace();
+        let flag = match self.peek() {
+            Some(b'0') => false,
+            Some(b'1') => true,
+            _ => return Err(self.error(PathDataErrorKind::ExpectedFlag)),
+        };
+        self.pos += 1;
+        self.skip_separator();
+        Ok(flag)
+    }
+}
+
+/// Parses an SVG path data string (the contents of a `d` attribute) into a
+/// sequence of [`PathCmd`]s.
+///
+/// All path commands are supported, in both absolute and relative forms,
+/// along with implicitly repeated commands and compact number syntax such as
+/// `M1.5.5-2e1`. Elliptical arcs are converted to conic segments using
+/// [`arc`].
+///
+/// ```
+/// use ochre::{parse_svg_path_data, PathCmd, Vec2};
+///
+/// let path = parse_svg_path_data("M10 10h20v20z").unwrap();
+/// assert_eq!(path[1], PathCmd::Line(Vec2::new(30.0, 10.0)));
+/// ```
+///
+/// [`PathCmd`]: crate::PathCmd
+/// [`arc`]: crate::arc
+pub fn parse_svg_path_data(data: &str) -> Result<Vec<PathCmd>, PathDataError> {
+    let mut parser = Parser { data: data.as_bytes(), pos: 0 };
+    let mut path = Path::new();
+
+    let mut command = None;
+    let mut closed = false;
+    let mut cubic_control = None;
+    let mut quad_control = None;
+
+    loop {
+        parser.skip_whitespace();
+        let next = match parser.peek() {
+            Some(next) => next,
+            None => break,
+        };
+
+        let start = parser.pos;
+        if next.is_ascii_alphabetic() {
+            parser.pos += 1;
+            command = Some(next);
+        } else {
+            command = match command {
+                Some(b'M') if parser.at_number() => Some(b'L'),
+                Some(b'm') if parser.at_number() => Some(b'l'),
+                Some(b'Z') | Some(b'z') | None => None,
+                _ if parser.at_number() => command,
+                _ => None,
+            };
+        }
+        let command = command.ok_or_else(|| parser.error(PathDataErrorKind::ExpectedCommand))?;
+
+        let relative = command.is_ascii_lowercase();
+        let upper = command.to_ascii_uppercase();
+        if path.is_empty() && upper != b'M' {
+            return Err(PathDataError { offset: start, kind: PathDataErrorKind::ExpectedMoveTo });
+        }
+        if upper != b'M' && upper != b'Z' && closed {
+            // A command following a closepath begins a new subpath at the
+            // same starting point.
+            let start = path.current_point();
+            path.move_to(start);
+        }
+        closed = false;
+
+        let current = path.current_point();
+        let offset = if relative { current } else { Vec2::new(0.0, 0.0) };
+        let mut next_cubic_control = None;
+        let mut next_quad_control = None;
+
+        match upper {
+            b'M' => {
+                path.move_to(offset + parser.point()?);
+            }
+            b'L' => {
+                path.line_to(offset + parser.point()?);
+            }
+            b'H' => {
+                let x = parser.number()?;
+                path.line_to(Vec2::new(offset.x + x, current.y));
+            }
+            b'V' => {
+                let y = parser.number()?;
+                path.line_to(Vec2::new(current.x, offset.y + y));
+            }
+            b'C' => {
+                let control1 = offset + parser.point()?;
+                let control2 = offset + parser.point()?;
+                let point = offset + parser.point()?;
+                path.cubic_to(control1, control2, point);
+                next_cubic_control = Some(control2);
+            }
+            b'S' => {
+                let control1 = match cubic_control {
+                    Some(control) => 2.0 * current - control,
+                    None => current,
+                };
+                let control2 = offset + parser.point()?;
+                let point = offset + parser.point()?;
+                path.cubic_to(control1, control2, point);
+                next_cubic_control = Some(control2);
+            }
+            b'Q' => {
+                let control = offset + parser.point()?;
+                let point = offset + parser.point()?;
+                path.quad_to(control, point);
+                next_quad_control = Some(control);
+            }
+            b'T' => {
+                let control = match quad_control {
+                    Some(control) => 2.0 * current - control,
+                    None => current,
+                };
+                let point = offset + parser.point()?;
+                path.quad_to(control, point);
+                next_quad_control = Some(control);
+            }
+            b'A' => {
+                let rx = parser.number()?;
+                let ry = parser.number()?;
+                let x_rotation = parser.number()?;
+                let large_arc = parser.flag()?;
+                let sweep = parser.flag()?;
+                let point = offset + parser.point()?;
+                path.arc_to(Vec2::new(rx, ry), x_rotation.to_radians(), large_arc, sweep, point);
+            }
+            b'Z' => {
+                path.close();
+                closed = true;
+            }
+            _ => {
+                return Err(PathDataError { offset: start, kind: PathDataErrorKind::ExpectedCommand });
+            }
+        }
+
+        cubic_control = next_cubic_control;
+        quad_control = next_quad_control;
+    }
+
+    Ok(path.into())
+}
+
+/// Options for [`to_svg_path_data`].
+///
+/// [`to_svg_path_data`]: crate::to_svg_path_data
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct PathDataOptions {
+    /// The maximum number of digits after the decimal point.
+    pub precision: usize,
+    /// Whether to output relative (lowercase) commands instead of absolute
+    /// ones.
+    pub relative: bool,
+}
+
+impl Default for PathDataOptions {
+    fn default() -> PathDataOptions {
+        PathDataOptions { precision: 3, relative: false }
+    }
+}
+
+struct Writer {
+    output: String,
+    options: PathDataOptions,
+    scale: f64,
+    last_command: Option<u8>,
+    last_has_point: bool,
+    current: [f64; 2],
+    start: [f64; 2],
+}
+
+impl Writer {
+    fn round(&self, point: Vec2) -> [f64; 2] {
+        [
+            (point.x as f64 * self.scale).round() / self.scale,
+            (point.y as f64 * self.scale).round() / self.scale,
+        ]
+    }
+
+    fn origin(&self) -> [f64; 2] {
+        if self.options.relative {
+            self.current
+        } else {
+            [0.0, 0.0]
+        }
+    }
+
+    fn command(&mut self, command: u8) {
+        let command = if self.options.relative { command.to_ascii_lowercase() } else { command };
+
+        // Omit the command letter when it would be implied by the previous
+        // command. A moveto is implicitly followed by lineto commands.
+        let implied = match self.last_command {
+            Some(b'M') => command == b'L',
+            Some(b'm') => command == b'l',
+            Some(b'Z') | Some(b'z') | None => false,
+            Some(last) => command == last,
+        };
+        if !implied {
+            self.output.push(command as char);
+            self.last_has_point = false;
+        }
+        self.last_command = Some(command);
+    }
+
+    fn number(&mut self, value: f64) {
+        let mut text = format!("{:.*}", self.options.precision, value);
+        if text.contains('.') {
+            let trimmed = text.trim_end_matches('0').trim_end_matches('.').len();
+            text.truncate(trimmed);
+        }
+        if text == "-0" {
+            text = String::from("0");
+        }
+        if text.starts_with("0.") {
+            text.remove(0);
+        } else if text.starts_with("-0.") {
+            text.remove(1);
+        }
+
+        // A separator is only needed if the number could otherwise be read
+        // as a continuation of the previous one.
+        let continues = text.starts_with('-') || text.starts_with('.') && self.last_has_point;
+        if self.output.ends_with(|c: char| c.is_ascii_digit()) && !continues {
+            self.output.push(' ');
+        }
+
+        self.last_has_point = text.contains('.');
+        self.output.push_str(&text);
+    }
+
+    fn points(&mut self, command: u8, points: &[Vec2]) {
+        self.command(command);
+        let origin = self.origin();
+        let mut end = self.current;
+        for &point in points {
+            end = self.round(point);
+            self.number(end[0] - origin[0]);
+            self.number(end[1] - origin[1]);
+        }
+        self.current = end;
+    }
+}
+
+/// Approximates a conic segment with cubic Bézier segments to within the given
+/// tolerance, subdividing it as necessary.
+fn conic_to_cubics(
+    last: Vec2,
+    control: Vec2,
+    point: Vec2,
+    weight: f32,
+    tolerance: f32,
+    depth: u32,
+    callback: &mut impl FnMut(Vec2, Vec2, Vec2),
+) {
+    // Empirical bound on the error of the cubic approximation below, which is
+    // exact at the endpoints and the midpoint of the segment.
+    let err = 5e-3 * (control - 0.5 * (last + point)).length() * (1.0 - weight) * (1.0 - weight);
+    if err > tolerance && depth < 16 {
+        let scale = 1.0 / (1.0 + weight);
+        let control1 = scale * (last + weight * control);
+        let control2 = scale * (weight * control + point);
+        let midpoint = 0.5 * (control1 + control2);
+        let weight = (0.5 * (1.0 + weight)).sqrt();
+        conic_to_cubics(last, control1, midpoint, weight, tolerance, depth + 1, callback);
+        conic_to_cubics(midpoint, control2, point, weight, tolerance, depth + 1, callback);
+    } else {
+        let k = 4.0 * weight / (3.0 * (1.0 + weight));
+        (callback)(last + k * (control - last), point + k * (control - point), point);
+    }
+}
+
+/// Converts a sequence of [`PathCmd`]s into an SVG path data string (the
+/// contents of a `d` attribute).
+///
+/// Coordinates are rounded to the precision given in the options, and the
+/// output is kept compact by omitting redundant command letters and
+/// separators. Since SVG has no rational quadratic command, conic segments are
+/// approximated with cubic Bézier segments to within the output precision.
+///
+/// ```
+/// use ochre::{to_svg_path_data, PathDataOptions, Path, Vec2};
+///
+/// let mut path = Path::new();
+/// path.rect(Vec2::new(10.0, 10.0), Vec2::new(20.0, 0.5));
+/// assert_eq!(to_svg_path_data(&path, PathDataOptions::default()), "M10 10H30V10.5H10Z");
+/// ```
+///
+/// [`PathCmd`]: crate::PathCmd
+pub fn to_svg_path_data(path: &[PathCmd], options: PathDataOptions) -> String {
+    let mut writer = Writer {
+        output: String::new(),
+        options,
+        scale: 10f64.powi(options.precision as i32),
+        last_command: None,
+        last_has_point: false,
+        current: [0.0, 0.0],
+        start: [0.0, 0.0],
+    };
+
+    let tolerance = 0.5 * 10f32.powi(-(options.precision as i32));
+
+    let mut last = Vec2::new(0.0, 0.0);
+    let mut start = Vec2::new(0.0, 0.0);
+    for command in path {
+        match *command {
+            PathCmd::Move(point) => {
+                writer.points(b'M', &[point]);
+                writer.start = writer.current;
+                start = point;
+                last = point;
+            }
+            PathCmd::Line(point) => {
+                let end = writer.round(point);
+                let origin = writer.origin();
+                if end[1] == writer.current[1] && end[0] != writer.current[0] {
+                    writer.command(b'H');
+                    writer.number(end[0] - origin[0]);
+                    writer.current = end;
+                } else if end[0] == writer.current[0] && end[1] != writer.current[1] {
+                    writer.command(b'V');
+                    writer.number(end[1] - origin[1]);
+                    writer.current = end;
+                } else {
+                    writer.points(b'L', &[point]);
+                }
+                last = point;
+            }
+            PathCmd::Quadratic(control, point) => {
+                writer.points(b'Q', &[control, point]);
+                last = point;
+            }
+            PathCmd::Cubic(control1, control2, point) => {
+                writer.points(b'C', &[control1, control2, point]);
+                last = point;
+            }
+            PathCmd::Conic(control, point, weight) => {
+                if weight == 1.0 {
+                    writer.points(b'Q', &[control, point]);
+                } else {
+                    conic_to_cubics(last, control, point, weight, tolerance, 0, &mut |control1, control2, point| {
+                        writer.points(b'C', &[control1, control2, point]);
+                    });
+                }
+                last = point;
+            }
+            PathCmd::Close => {
+                writer.command(b'Z');
+                writer.current = writer.start;
+                last = start;
+            }
+        }
+    }
+
+    writer.output
+}
@@ -0,0 +1,412 @@
+use crate::{TileBuilder, TILE_SIZE};
+
+/// An alpha mask tile, as produced by a [`Rasterizer`].
+///
+/// [`Rasterizer`]: crate::Rasterizer
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Tile {
+    pub x: i16,
+    pub y: i16,
+    pub data: [u8; TILE_SIZE * TILE_SIZE],
+}
+
+/// A solid interior span, as produced by a [`Rasterizer`].
+///
+/// [`Rasterizer`]: crate::Rasterizer
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Span {
+    pub x: i16,
+    pub y: i16,
+    pub width: u16,
+}
+
+/// A recorded set of alpha mask tiles and solid spans.
+///
+/// A `TileList` can be passed to [`Rasterizer::finish`] to record its output,
+/// combined with other tile lists using boolean operations, and then replayed
+/// into another [`TileBuilder`] using [`TileList::build`].
+///
+/// The boolean operations expect tiles and spans to lie on the tile grid and
+/// not to overlap each other, as is the case for the output of a single
+/// rasterizer.
+///
+/// [`Rasterizer::finish`]: crate::Rasterizer::finish
+/// [`TileBuilder`]: crate::TileBuilder
+#[derive(Clone, Debug, Default)]
+pub struct TileList {
+    pub tiles: Vec<Tile>,
+    pub spans: Vec<Span>,
+}
+
+impl TileBuilder for TileList {
+    fn tile(&mut self, x: i16, y: i16, data: [u8; TILE_SIZE * TILE_SIZE]) {
+        self.tiles.push(Tile { x, y, data });
+    }
+
+    fn span(&mut self, x: i16, y: i16, width: u16) {
+        self.spans.push(Span { x, y, width });
+    }
+}
+
+#[derive(Copy, Clone)]
+enum BooleanOp {
+    Intersect,
+    Union,
+    Difference,
+    Xor,
+}
+
+impl BooleanOp {
+    #[inline]
+    fn apply(self, a: u8, b: u8) -> u8 {
+        let ab = ((a as u32 * b as u32 + 127) / 255) as u8;
+        match self {
+            BooleanOp::Intersect => ab,
+            BooleanOp::Union => a + (b - ab),
+            BooleanOp::Difference => a - ab,
+            BooleanOp::Xor => (a - ab) + (b - ab),
+        }
+    }
+}
+
+#[derive(Copy, Clone)]
+enum Coverage<'a> {
+    Empty,
+    Solid,
+    Mask(&'a [u8; TILE_SIZE * TILE_SIZE]),
+}
+
+impl<'a> Coverage<'a> {
+    #[inline]
+    fn pixel(self, i: usize) -> u8 {
+        match self {
+            Coverage::Empty => 0,
+            Coverage::Solid => 255,
+            Coverage::Mask(data) => data[i],
+        }
+    }
+}
+
+/// A run of tile columns `start..end` within a single row of tiles.
+#[derive(Copy, Clone)]
+struct Run<'a> {
+    row: i16,
+    start: i32,
+    end: i32,
+    coverage: Coverage<'a>,
+}
+
+impl TileList {
+    /// Creates an empty tile list.
+    pub fn new() -> TileList {
+        TileList::default()
+    }
+
+    /// Removes all tiles and spans.
+    pub fn clear(&mut self) {
+        self.tiles.clear();
+        self.spans.clear();
+    }
+
+    /// Returns `true` if the tile list contains no tiles or spans.
+    pub fn is_empty(&self) -> bool {
+        self.tiles.is_empty() && self.spans.is_empty()
+    }
+
+    /// Passes the recorded tiles and spans to the given [`TileBuilder`].
+    ///
+    /// [`TileBuilder`]: crate::TileBuilder
+    pub fn build<B: TileBuilder>(&self, builder: &mut B) {
+        for tile in &self.tiles {
+            builder.tile(tile.x, tile.y, tile.data);
+        }
+        for span in &self.spans {
+            builder.span(span.x, span.y, span.width);
+        }
+    }
+
+    /// Computes the coverage covered by both tile lists.
+    ///
+    /// ```
+    /// use ochre::{Span, TileList};
+    ///
+    /// let mut a = TileList::new();
+    /// a.spans.push(Span { x: 0, y: 8, width: 24 });
+    ///
+    /// let mut b = TileList::new();
+    /// b.spans.push(Span { x: 8, y: 8, width: 32 });
+    ///
+    /// let result = a.intersect(&b);
+    /// assert_eq!(result.spans, [Span { x: 8, y: 8, width: 16 }]);
+    /// assert!(result.tiles.is_empty());
+    /// ```
+    pub fn intersect(&self, other: &TileList) -> TileList {
+        self.combine(other, BooleanOp::Intersect)
+    }
+
+    /// Computes the coverage covered by either tile list.
+    pub fn union(&self, other: &TileList) -> TileList {
+        self.combine(other, BooleanOp::Union)
+    }
+
+    /// Computes the coverage covered by this tile list but not by `other`.
+    ///
+    /// Spans are split around tiles where the result is only partially
+    /// covered:
+    ///
+    /// ```
+    /// use ochre::{Span, Tile, TileList, TILE_SIZE};
+    ///
+    /// let mut a = TileList::new();
+    /// a.spans.push(Span { x: 0, y: 0, width: 24 });
+    ///
+    /// let mut b = TileList::new();
+    /// b.tiles.push(Tile { x: 8, y: 0, data: [128; TILE_SIZE * TILE_SIZE] });
+    ///
+    /// let result = a.difference(&b);
+    /// assert_eq!(result.spans, [Span { x: 0, y: 0, width: 8 }, Span { x: 16, y: 0, width: 8 }]);
+    /// assert_eq!(result.tiles, [Tile { x: 8, y: 0, data: [127; TILE_SIZE * TILE_SIZE] }]);
+    /// ```
+    pub fn difference(&self, other: &TileList) -> TileList {
+        self.combine(other, BooleanOp::Difference)
+    }
+
+    /// Computes the coverage covered by exactly one of the tile lists.
+    pub fn xor(&self, other: &TileList) -> TileList {
+        self.combine(other, BooleanOp::Xor)
+    }
+
+    /// Prepares the tile list for looking up coverage by tile position.
+    pub(crate) fn mask(&self) -> Mask<'_> {
+        Mask { runs: self.runs() }
+    }
+
+    fn runs(&self) -> Vec<Run<'_>> {
+        let mut runs = Vec::with_capacity(self.tiles.len() + self.spans.len());
+        for tile in &self.tiles {
+            let start = tile.x.div_euclid(TILE_SIZE as i16) as i32;
+            runs.push(Run {
+                row: tile.y.div_euclid(TILE_SIZE as i16),
+                start,
+                end: start + 1,
+                coverage: Coverage::Mask(&tile.data),
+            });
+        }
+        for span in self.spans.iter().filter(|span| span.width > 0) {
+            let start = span.x.div_euclid(TILE_SIZE as i16) as i32;
+            runs.push(Run {
+                row: span.y.div_euclid(TILE_SIZE as i16),
+                start,
+                end: start + (span.width as i32 + TILE_SIZE as i32 - 1) / TILE_SIZE as i32,
+                coverage: Coverage::Solid,
+            });
+        }
+        runs.sort_unstable_by_key(|run| (run.row, run.start));
+        runs
+    }
+
+    fn combine(&self, other: &TileList, op: BooleanOp) -> TileList {
+        let a = self.runs();
+        let b = other.runs();
+
+        let mut output = TileList::new();
+        let mut i = 0;
+        let mut j = 0;
+        while i < a.len() || j < b.len() {
+            let row = match (a.get(i), b.get(j)) {
+                (Some(run_a), Some(run_b)) => run_a.row.min(run_b.row),
+                (Some(run_a), None) => run_a.row,
+                (None, Some(run_b)) => run_b.row,
+                (None, None) => unreachable!(),
+            };
+            let a_end = i + a[i..].iter().take_while(|run| run.row == row).count();
+            let b_end = j + b[j..].iter().take_while(|run| run.row == row).count();
+
+            output.combine_row(row, &a[i..a_end], &b[j..b_end], op);
+
+            i = a_end;
+            j = b_end;
+        }
+
+        output
+    }
+
+    /// Sweeps across a single row of tiles, combining the runs from both
+    /// inputs. Solid and empty regions are combined as whole intervals, so
+    /// only tile columns where at least one input has an alpha mask are
+    /// combined per pixel.
+    fn combine_row(&mut self, row: i16, a: &[Run], b: &[Run], op: BooleanOp) {
+        #[inline]
+        fn active<'a>(run: Option<&Run<'a>>, pos: i32, start: i32, end: &mut i32) -> Coverage<'a> {
+            match run {
+                Some(run) if run.start.max(pos) <= start => {
+                    *end = (*end).min(run.end);
+                    run.coverage
+                }
+                Some(run) => {
+                    *end = (*end).min(run.start.max(pos));
+                    Coverage::Empty
+                }
+                None => Coverage::Empty,
+            }
+        }
+
+        let y = row * TILE_SIZE as i16;
+        let mut span: Option<(i32, i32)> = None;
+
+        let mut i = 0;
+        let mut j = 0;
+        let mut pos = i32::MIN;
+        while i < a.len() || j < b.len() {
+            let start_a = a.get(i).map_or(i32::MAX, |run| run.start.max(pos));
+            let start_b = b.get(j).map_or(i32::MAX, |run| run.start.max(pos));
+            let start = start_a.min(start_b);
+
+            let mut end = i32::MAX;
+            let coverage_a = active(a.get(i), pos, start, &mut end);
+            let coverage_b = active(b.get(j), pos, start, &mut end);
+
+            let solid = match (coverage_a, coverage_b) {
+                (Coverage::Mask(_), _) | (_, Coverage::Mask(_)) => {
+                    let mut data = [0; TILE_SIZE * TILE_SIZE];
+                    for (k, pixel) in data.iter_mut().enumerate() {
+                        *pixel = op.apply(coverage_a.pixel(k), coverage_b.pixel(k));
+                    }
+
+                    if data.iter().all(|&pixel| pixel == 255) {
+                        true
+                    } else {
+                        if data.iter().any(|&pixel| pixel != 0) {
+                            self.tiles.push(Tile { x: start as i16 * TILE_SIZE as i16, y, data });
+                        }
+                        false
+                    }
+                }
+                _ => op.apply(coverage_a.pixel(0), coverage_b.pixel(0)) == 255,
+            };
+
+            match span {
+                Some((_, ref mut span_end)) if solid && *span_end == start => {
+                    *span_end = end;
+                }
+                _ => {
+                    if let Some((span_start, span_end)) = span.take() {
+                        self.push_span(span_start, span_end, y);
+                    }
+                    if solid {
+                        span = Some((start, end));
+                    }
+                }
+            }
+
+            pos = end;
+            if i < a.len() && a[i].end <= end {
+                i += 1;
+            }
+            if j < b.len() && b[j].end <= end {
+                j += 1;
+            }
+        }
+
+        if let Some((span_start, span_end)) = span {
+            self.push_span(span_start, span_end, y);
+        }
+    }
+
+    fn push_span(&mut self, start: i32, end: i32, y: i16) {
+        self.spans.push(Span {
+            x: start as i16 * TILE_SIZE as i16,
+            y,
+            width: (end - start) as u16 * TILE_SIZE as u16,
+        });
+    }
+}
+
+/// A tile list with its tiles and spans sorted by position.
+pub(crate) struct Mask<'a> {
+    runs: Vec<Run<'a>>,
+}
+
+impl<'a> Mask<'a> {
+    /// Finds the index of the first run in the given row which ends after the
+    /// given tile column.
+    fn find(&self, row: i16, col: i32) -> usize {
+        self.runs.partition_point(|run| (run.row, run.end) <= (row, col))
+    }
+
+    /// Wraps a [`TileBuilder`] so that everything passed to it is intersected
+    /// with the mask.
+    ///
+    /// [`TileBuilder`]: crate::TileBuilder
+    pub(crate) fn clip<'b, B: TileBuilder>(&'b self, builder: &'b mut B) -> Clipped<'a, 'b, B> {
+        Clipped { mask: self, builder }
+    }
+}
+
+/// A [`TileBuilder`] adapter which intersects tiles and spans with a [`Mask`].
+///
+/// Tiles and spans which fall entirely outside the mask are dropped, and parts
+/// of spans which are partially covered by the mask are turned into tiles.
+///
+/// [`TileBuilder`]: crate::TileBuilder
+pub(crate) struct Clipped<'a, 'b, B> {
+    mask: &'b Mask<'a>,
+    builder: &'b mut B,
+}
+
+impl<'a, 'b, B: TileBuilder> TileBuilder for Clipped<'a, 'b, B> {
+    fn tile(&mut self, x: i16, y: i16, data: [u8; TILE_SIZE * TILE_SIZE]) {
+        let row = y.div_euclid(TILE_SIZE as i16);
+        let col = x.div_euclid(TILE_SIZE as i16) as i32;
+        let coverage = match self.mask.runs.get(self.mask.find(row, col)) {
+            Some(run) if run.row == row && run.start <= col => run.coverage,
+            _ => Coverage::Empty,
+        };
+
+        match coverage {
+            Coverage::Empty => {}
+            Coverage::Solid => {
+                self.builder.tile(x, y, data);
+            }
+            Coverage::Mask(mask) => {
+                let mut clipped = [0; TILE_SIZE * TILE_SIZE];
+                for (k, pixel) in clipped.iter_mut().enumerate() {
+                    *pixel = BooleanOp::Intersect.apply(data[k], mask[k]);
+                }
+                if clipped.iter().any(|&pixel| pixel != 0) {
+                    self.builder.tile(x, y, clipped);
+                }
+            }
+        }
+    }
+
+    fn span(&mut self, x: i16, y: i16, width: u16) {
+        let row = y.div_euclid(TILE_SIZE as i16);
+        let start = x.div_euclid(TILE_SIZE as i16) as i32;
+        let end = start + (width as i32 + TILE_SIZE as i32 - 1) / TILE_SIZE as i32;
+
+        for run in &self.mask.runs[self.mask.find(row, start)..] {
+            if run.row != row || run.start >= end {
+                break;
+            }
+
+            let clipped_start = run.start.max(start);
+            let clipped_end = run.end.min(end);
+            match run.coverage {
+                Coverage::Empty => {}
+                Coverage::Solid => {
+                    self.builder.span(
+                        clipped_start as i16 * TILE_SIZE as i16,
+                        y,
+                        (clipped_end - clipped_start) as u16 * TILE_SIZE as u16,
+                    );
+                }
+                Coverage::Mask(mask) => {
+                    if mask.iter().any(|&pixel| pixel != 0) {
+                        self.builder.tile(clipped_start as i16 * TILE_SIZE as i16, y, *mask);
+                    }
+                }
+            }
+        }
+    }
+}